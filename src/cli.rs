@@ -0,0 +1,358 @@
+use crate::color::ColorChoice;
+use crate::policy::parse_port_list;
+use crate::profiles::ScanProfile;
+use crate::report::ReportFormat;
+use crate::server::DEFAULT_LISTEN;
+
+pub const USAGE: &str = "\
+Usage:
+  vonogs                  Start the interactive menu
+  vonogs scan <target>... [-p <ports> | --profile <name>] [--quiet | --grepable]
+                          Scan without prompts; --quiet prints open host:port lines,
+                          --grepable one nmap-style line per host
+  vonogs policy <file> [--sarif <out>] [--junit <out>]
+                          Scan hosts from a policy file and exit non-zero on violations
+  vonogs watch <file>     Rescan hosts from a watch file on an interval and report changes
+  vonogs serve [--listen <addr>]
+                          Run the REST API (default 127.0.0.1:8088)
+  vonogs report [--format html|md|sarif] [-o <file>] <log>...
+                          Build one HTML, Markdown or SARIF report from saved scan logs
+  vonogs webhook-test     Send a test event to VONOGS_WEBHOOK_URL
+  vonogs help             Show this help
+
+Options:
+  --color=auto|always|never
+                          Colour and redraw output; `auto` (default) turns both
+                          off when stdout is not a terminal or NO_COLOR is set
+  -v, -vv                 Trace every probe to stderr: address, outcome and error
+                          kind; -vv adds resolution and raw OS errors
+  --proxy <url>           Connect through socks5:// or http://[user:pass@]host:port
+
+Exit codes for `policy`:
+  0  every host matches its policy
+  1  at least one policy violation
+  2  the policy file or a target could not be used";
+
+/// What `vonogs scan` prints to stdout; the log is written either way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScanOutput {
+    Normal,
+    Quiet,
+    Grepable,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Interactive,
+    Scan {
+        targets: Vec<String>,
+        scan_type: String,
+        ports: Vec<u16>,
+        output: ScanOutput,
+    },
+    Policy {
+        file: String,
+        sarif: Option<String>,
+        junit: Option<String>,
+    },
+    Watch {
+        file: String,
+    },
+    Serve {
+        listen: String,
+    },
+    Report {
+        logs: Vec<String>,
+        output: Option<String>,
+        format: ReportFormat,
+    },
+    WebhookTest,
+    Help,
+}
+
+impl Command {
+    /// Whether the command looks up names or connects anywhere, and so
+    /// needs `VONOGS_DNS_SERVER` and `--proxy` to be usable.
+    pub fn uses_network(&self) -> bool {
+        !matches!(self, Command::Report { .. } | Command::Help)
+    }
+}
+
+/// Options accepted before or after any command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobalOptions {
+    pub color: ColorChoice,
+    pub verbosity: u8,
+    /// The `--proxy` URL, checked when the proxy is set up.
+    pub proxy: Option<String>,
+}
+
+/// Removes `--color=<when>`, `--color <when>`, `--proxy <url>`, `-v`, `-vv`
+/// and `--verbose` from anywhere in `args`.
+pub fn take_global_options(args: &mut Vec<String>) -> Result<GlobalOptions, String> {
+    let mut options = GlobalOptions {
+        color: ColorChoice::Auto,
+        verbosity: 0,
+        proxy: None,
+    };
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        if arg == "--verbose" {
+            options.verbosity = options.verbosity.saturating_add(1);
+            args.remove(i);
+            continue;
+        }
+        if arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| c == 'v') {
+            let count = arg.len().saturating_sub(1).min(usize::from(u8::MAX)) as u8;
+            options.verbosity = options.verbosity.saturating_add(count);
+            args.remove(i);
+            continue;
+        }
+
+        if let Some(url) = arg.strip_prefix("--proxy=") {
+            options.proxy = Some(url.to_string());
+            args.remove(i);
+            continue;
+        }
+        if arg == "--proxy" {
+            args.remove(i);
+            if i == args.len() {
+                return Err(String::from(
+                    "--proxy needs a URL, e.g. socks5://127.0.0.1:1080",
+                ));
+            }
+            options.proxy = Some(args.remove(i));
+            continue;
+        }
+
+        let value = if let Some(value) = arg.strip_prefix("--color=") {
+            let value = value.to_string();
+            args.remove(i);
+            value
+        } else if arg == "--color" {
+            args.remove(i);
+            if i == args.len() {
+                return Err(String::from("--color needs auto, always or never"));
+            }
+            args.remove(i)
+        } else {
+            i += 1;
+            continue;
+        };
+        options.color = ColorChoice::from_name(&value)
+            .ok_or_else(|| format!("--color: unknown value '{}'", value))?;
+    }
+    Ok(options)
+}
+
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let mut args = args.iter();
+    let command = match args.next() {
+        None => return Ok(Command::Interactive),
+        Some(command) => command.as_str(),
+    };
+
+    let command = match command {
+        "scan" => return parse_scan_args(args),
+        "policy" => return parse_policy_args(args),
+        "watch" => {
+            let file = args
+                .next()
+                .ok_or_else(|| String::from("watch: missing watch file"))?;
+            Command::Watch { file: file.clone() }
+        }
+        "serve" => match args.next().map(String::as_str) {
+            None => Command::Serve {
+                listen: DEFAULT_LISTEN.to_string(),
+            },
+            Some("--listen") => {
+                let listen = args
+                    .next()
+                    .ok_or_else(|| String::from("serve: --listen needs an address"))?;
+                Command::Serve {
+                    listen: listen.clone(),
+                }
+            }
+            Some(other) => return Err(format!("serve: unexpected argument '{}'", other)),
+        },
+        "report" => return parse_report_args(args),
+        "webhook-test" => Command::WebhookTest,
+        "help" | "-h" | "--help" => Command::Help,
+        other => return Err(format!("unknown command '{}'", other)),
+    };
+
+    match args.next() {
+        Some(extra) => Err(format!("unexpected argument '{}'", extra)),
+        None => Ok(command),
+    }
+}
+
+fn parse_report_args<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+    let mut logs = Vec::new();
+    let mut output: Option<String> = None;
+    let mut format = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-f" | "--format" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| format!("report: {} needs html, md or sarif", arg))?;
+                format = Some(
+                    ReportFormat::from_name(name)
+                        .ok_or_else(|| format!("report: unknown format '{}'", name))?,
+                );
+            }
+            "-o" | "--output" => {
+                let file = args
+                    .next()
+                    .ok_or_else(|| format!("report: {} needs a file name", arg))?;
+                output = Some(file.clone());
+            }
+            other if other.starts_with('-') => {
+                return Err(format!("report: unknown option '{}'", other))
+            }
+            log => logs.push(log.to_string()),
+        }
+    }
+    if logs.is_empty() {
+        return Err(String::from("report: no log files given"));
+    }
+    let format = format
+        .or_else(|| output.as_deref().and_then(ReportFormat::from_path))
+        .unwrap_or(ReportFormat::Html);
+    Ok(Command::Report {
+        logs,
+        output,
+        format,
+    })
+}
+
+fn parse_scan_args<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+    let mut targets = Vec::new();
+    let mut ports: Option<(String, Vec<u16>)> = None;
+    let mut output = ScanOutput::Normal;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-p" | "--ports" | "--profile" if ports.is_some() => {
+                return Err(String::from("scan: give either --ports or --profile, once"))
+            }
+            "-p" | "--ports" => {
+                let list = args
+                    .next()
+                    .ok_or_else(|| format!("scan: {} needs a port list", arg))?;
+                let mut list = parse_port_list(list).map_err(|e| format!("scan: {}", e))?;
+                list.sort_unstable();
+                list.dedup();
+                ports = Some((String::from("Custom Port List"), list));
+            }
+            "--profile" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| format!("scan: {} needs a profile name", arg))?;
+                let profile = ScanProfile::from_name(name)
+                    .ok_or_else(|| format!("scan: unknown profile '{}'", name))?;
+                ports = Some((profile.get_name().to_string(), profile.get_ports()));
+            }
+            "-q" | "--quiet" | "--grepable" if output != ScanOutput::Normal => {
+                return Err(String::from(
+                    "scan: --quiet and --grepable cannot be combined",
+                ))
+            }
+            "-q" | "--quiet" => output = ScanOutput::Quiet,
+            "--grepable" => output = ScanOutput::Grepable,
+            other if other.starts_with('-') => {
+                return Err(format!("scan: unknown option '{}'", other))
+            }
+            list => targets.extend(
+                list.split(',')
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(String::from),
+            ),
+        }
+    }
+    if targets.is_empty() {
+        return Err(String::from("scan: no targets given"));
+    }
+    let (scan_type, ports) = ports.unwrap_or_else(|| {
+        (
+            ScanProfile::Quick.get_name().to_string(),
+            ScanProfile::Quick.get_ports(),
+        )
+    });
+    if ports.is_empty() {
+        return Err(String::from("scan: no ports given"));
+    }
+    Ok(Command::Scan {
+        targets,
+        scan_type,
+        ports,
+        output,
+    })
+}
+
+fn parse_policy_args<'a>(mut args: impl Iterator<Item = &'a String>) -> Result<Command, String> {
+    let mut file = None;
+    let mut sarif = None;
+    let mut junit = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sarif" => {
+                let out = args
+                    .next()
+                    .ok_or_else(|| format!("policy: {} needs a file name", arg))?;
+                sarif = Some(out.clone());
+            }
+            "--junit" => {
+                let out = args
+                    .next()
+                    .ok_or_else(|| format!("policy: {} needs a file name", arg))?;
+                junit = Some(out.clone());
+            }
+            other if other.starts_with('-') => {
+                return Err(format!("policy: unknown option '{}'", other))
+            }
+            path if file.is_none() => file = Some(path.to_string()),
+            extra => return Err(format!("unexpected argument '{}'", extra)),
+        }
+    }
+    let file = file.ok_or_else(|| String::from("policy: missing policy file"))?;
+    Ok(Command::Policy { file, sarif, junit })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn counts_verbosity_flags() {
+        let mut list = args(&["vonogs", "-v", "scan", "-vv", "--verbose", "10.0.0.1"]);
+        let options = take_global_options(&mut list).unwrap();
+        assert_eq!(options.verbosity, 4);
+        assert_eq!(list, args(&["vonogs", "scan", "10.0.0.1"]));
+    }
+
+    #[test]
+    fn long_verbosity_flag_saturates() {
+        for count in [255, 256, 300] {
+            let mut list = args(&["vonogs", &format!("-{}", "v".repeat(count))]);
+            let options = take_global_options(&mut list).unwrap();
+            assert_eq!(options.verbosity, u8::MAX);
+        }
+    }
+
+    #[test]
+    fn only_network_commands_need_dns_and_proxy() {
+        let command = |list: &[&str]| parse_args(&args(list)).unwrap();
+        assert!(!command(&["help"]).uses_network());
+        assert!(!command(&["report", "scan.log"]).uses_network());
+        assert!(command(&["scan", "10.0.0.1", "-p", "22"]).uses_network());
+        assert!(command(&["webhook-test"]).uses_network());
+        assert!(command(&[]).uses_network());
+    }
+}
@@ -0,0 +1,162 @@
+use std::io::{self, IsTerminal};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The `--color` setting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn from_name(name: &str) -> Option<ColorChoice> {
+        match name {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+}
+
+// Raw codes for output that is only drawn when colour is on, like the
+// dashboard and the redrawn progress line; other output goes through the
+// functions below, which respect `--color`.
+pub const RESET: &str = "\x1b[0m";
+pub const BOLD: &str = "\x1b[1m";
+pub const DIM: &str = "\x1b[2m";
+pub const REVERSE: &str = "\x1b[7m";
+pub const RED: &str = "\x1b[31m";
+pub const GREEN: &str = "\x1b[32m";
+pub const YELLOW: &str = "\x1b[33m";
+pub const MAGENTA: &str = "\x1b[35m";
+pub const CYAN: &str = "\x1b[36m";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static REDRAW: AtomicBool = AtomicBool::new(false);
+
+/// `auto` colours a terminal unless `NO_COLOR` is set to a non-empty value
+/// or `TERM` is `dumb`; `always` and `never` override both.
+pub fn init(choice: ColorChoice) {
+    let tty = io::stdout().is_terminal();
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            tty && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                && std::env::var("TERM").map_or(true, |t| t != "dumb")
+        }
+    };
+    ENABLED.store(enabled, Ordering::Relaxed);
+    REDRAW.store(tty && enabled, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Whether output may move the cursor: progress lines redrawn with `\r`,
+/// transient status text and screen clears. Off in plain mode, where every
+/// line printed stays as it is.
+pub fn redraw() -> bool {
+    REDRAW.load(Ordering::Relaxed)
+}
+
+fn paint(code: &'static str) -> &'static str {
+    if enabled() {
+        code
+    } else {
+        ""
+    }
+}
+
+pub fn reset() -> &'static str {
+    paint(RESET)
+}
+
+pub fn red() -> &'static str {
+    paint(RED)
+}
+
+pub fn green() -> &'static str {
+    paint(GREEN)
+}
+
+pub fn yellow() -> &'static str {
+    paint(YELLOW)
+}
+
+pub fn magenta() -> &'static str {
+    paint(MAGENTA)
+}
+
+pub fn cyan() -> &'static str {
+    paint(CYAN)
+}
+
+pub fn bold() -> &'static str {
+    paint(BOLD)
+}
+
+/// Runs `stty` on the terminal behind stdin and returns its output.
+pub fn stty(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("could not run stty: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from("stty failed"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Rows and columns of the terminal behind stdin.
+pub fn stty_size() -> Option<(usize, usize)> {
+    let size = stty(&["size"]).ok()?;
+    let (rows, cols) = size.split_once(' ')?;
+    Some((rows.parse().ok()?, cols.parse().ok()?)).filter(|&(rows, cols)| rows > 0 && cols > 0)
+}
+
+/// Width of the controlling terminal, when stdin is one.
+pub fn terminal_width() -> Option<usize> {
+    stty_size().map(|(_, cols)| cols)
+}
+
+/// Columns a line takes up once ANSI sequences are left out.
+pub fn visible_width(line: &str) -> usize {
+    let mut width = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+/// Cuts a line to `width` visible columns, keeping ANSI sequences intact.
+pub fn fit(line: &str, width: usize) -> String {
+    let mut fitted = String::with_capacity(line.len());
+    let mut visible = 0;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            fitted.push(c);
+            for c in chars.by_ref() {
+                fitted.push(c);
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else if visible < width {
+            fitted.push(c);
+            visible += 1;
+        }
+    }
+    fitted
+}
@@ -0,0 +1,487 @@
+use crate::trace;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, UdpSocket};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+
+/// How long to wait for each answer, and how often a UDP query is sent
+/// before the server counts as not answering.
+const TIMEOUT: Duration = Duration::from_secs(2);
+const UDP_ATTEMPTS: usize = 2;
+
+/// The server from `VONOGS_DNS_SERVER`, as `10.0.0.53`, `10.0.0.53:5353`
+/// or `[fd00::53]:53`; `None` uses the OS resolver.
+pub fn server_from_env() -> Result<Option<SocketAddr>, String> {
+    match std::env::var("VONOGS_DNS_SERVER") {
+        Ok(value) if !value.trim().is_empty() => {
+            parse_server(value.trim()).map(Some).ok_or_else(|| {
+                format!(
+                    "VONOGS_DNS_SERVER must be an IP address with an optional port, not '{}'",
+                    value.trim()
+                )
+            })
+        }
+        _ => Ok(None),
+    }
+}
+
+fn parse_server(value: &str) -> Option<SocketAddr> {
+    value.parse::<SocketAddr>().ok().or_else(|| {
+        value
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+            .ok()
+            .map(|ip| SocketAddr::new(ip, 53))
+    })
+}
+
+/// The server asked for PTR records: `VONOGS_DNS_SERVER`, otherwise the
+/// first `nameserver` in `/etc/resolv.conf`, since the OS resolver offers
+/// no reverse lookups through `std`.
+pub fn reverse_server() -> Option<SocketAddr> {
+    if let Ok(Some(server)) = server_from_env() {
+        return Some(server);
+    }
+    std::fs::read_to_string("/etc/resolv.conf")
+        .ok()?
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .find_map(|value| parse_server(value.trim()))
+}
+
+enum Data {
+    Address(IpAddr),
+    Name(String),
+    Other,
+}
+
+struct Record {
+    ttl: u32,
+    data: Data,
+}
+
+/// The A and AAAA records of `name`, and the lowest TTL among them.
+pub fn lookup(server: SocketAddr, name: &str) -> Result<(Vec<IpAddr>, Duration), String> {
+    let mut addrs: Vec<IpAddr> = Vec::new();
+    let mut ttl: Option<u32> = None;
+    let mut error = None;
+    for qtype in [TYPE_A, TYPE_AAAA] {
+        match query(server, name, qtype) {
+            Ok(records) => {
+                for record in records {
+                    if let Data::Address(ip) = record.data {
+                        if !addrs.contains(&ip) {
+                            addrs.push(ip);
+                        }
+                        ttl = Some(ttl.map_or(record.ttl, |t| t.min(record.ttl)));
+                    }
+                }
+            }
+            Err(e) => error = Some(e),
+        }
+    }
+    match ttl {
+        Some(ttl) => Ok((addrs, Duration::from_secs(u64::from(ttl)))),
+        None => Err(error.unwrap_or_else(|| String::from("no A or AAAA records"))),
+    }
+}
+
+/// The PTR name of `ip`, without the trailing dot; `None` when there is
+/// none or the server did not answer.
+pub fn reverse(server: SocketAddr, ip: IpAddr) -> Option<String> {
+    match query(server, &reverse_name(ip), TYPE_PTR) {
+        Ok(records) => records.into_iter().find_map(|record| match record.data {
+            Data::Name(name) => Some(name),
+            _ => None,
+        }),
+        Err(e) => {
+            trace::log(2, &format!("[vv] no reverse DNS for {}: {}", ip, e));
+            None
+        }
+    }
+}
+
+/// `4.3.2.1.in-addr.arpa` for 1.2.3.4, and the nibble form under
+/// `ip6.arpa` for IPv6.
+fn reverse_name(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, d] = v4.octets();
+            format!("{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+        }
+        IpAddr::V6(v6) => {
+            let mut name = String::new();
+            for byte in v6.octets().iter().rev() {
+                name.push_str(&format!("{:x}.{:x}.", byte & 0x0f, byte >> 4));
+            }
+            name.push_str("ip6.arpa");
+            name
+        }
+    }
+}
+
+fn query(server: SocketAddr, name: &str, qtype: u16) -> Result<Vec<Record>, String> {
+    let id = query_id();
+    let packet = encode_query(id, name, qtype)?;
+    trace::log(
+        2,
+        &format!("[vv] asking {} for {} ({})", server, name, type_name(qtype)),
+    );
+    let response = exchange_udp(server, &packet)?;
+    let response = if response[2] & 0x02 != 0 {
+        trace::log(2, "[vv] UDP answer truncated, asking again over TCP");
+        exchange_tcp(server, &packet)?
+    } else {
+        response
+    };
+    parse_response(&response)
+}
+
+fn type_name(qtype: u16) -> &'static str {
+    match qtype {
+        TYPE_A => "A",
+        TYPE_PTR => "PTR",
+        TYPE_AAAA => "AAAA",
+        _ => "?",
+    }
+}
+
+/// Not cryptographic; only has to differ between queries so late answers
+/// to an earlier query are not taken for this one.
+fn query_id() -> u16 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    (nanos ^ (nanos >> 16) ^ std::process::id()) as u16
+}
+
+fn encode_query(id: u16, name: &str, qtype: u16) -> Result<Vec<u8>, String> {
+    let invalid = || format!("'{}' is not a valid DNS name", name);
+    let mut packet = Vec::with_capacity(512);
+    packet.extend_from_slice(&id.to_be_bytes());
+    // Recursion desired; one question, no other records.
+    packet.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(invalid());
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    if packet.len() - 12 > 255 {
+        return Err(invalid());
+    }
+    packet.extend_from_slice(&qtype.to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(packet)
+}
+
+fn exchange_udp(server: SocketAddr, packet: &[u8]) -> Result<Vec<u8>, String> {
+    let local = if server.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(local).map_err(|e| e.to_string())?;
+    socket
+        .set_read_timeout(Some(TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    socket.connect(server).map_err(|e| e.to_string())?;
+
+    let mut buffer = [0u8; 4096];
+    for _ in 0..UDP_ATTEMPTS {
+        socket.send(packet).map_err(|e| e.to_string())?;
+        loop {
+            match socket.recv(&mut buffer) {
+                Ok(len) if len >= 12 && buffer[..2] == packet[..2] => {
+                    return Ok(buffer[..len].to_vec());
+                }
+                // A stray or late datagram; keep waiting for ours.
+                Ok(_) => continue,
+                Err(e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut =>
+                {
+                    break
+                }
+                Err(e) => return Err(format!("DNS server {}: {}", server, e)),
+            }
+        }
+    }
+    Err(format!("no answer from DNS server {}", server))
+}
+
+/// Same query over TCP, each message prefixed with its length.
+fn exchange_tcp(server: SocketAddr, packet: &[u8]) -> Result<Vec<u8>, String> {
+    let failed = |e: io::Error| format!("DNS server {} over TCP: {}", server, e);
+    let mut stream = TcpStream::connect_timeout(&server, TIMEOUT).map_err(failed)?;
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(failed)?;
+    stream.set_write_timeout(Some(TIMEOUT)).map_err(failed)?;
+
+    let mut message = (packet.len() as u16).to_be_bytes().to_vec();
+    message.extend_from_slice(packet);
+    stream.write_all(&message).map_err(failed)?;
+
+    let mut len = [0u8; 2];
+    stream.read_exact(&mut len).map_err(failed)?;
+    let mut response = vec![0u8; usize::from(u16::from_be_bytes(len))];
+    stream.read_exact(&mut response).map_err(failed)?;
+    if response.len() < 12 || response[..2] != packet[..2] {
+        return Err(format!(
+            "DNS server {} sent a mismatched TCP answer",
+            server
+        ));
+    }
+    Ok(response)
+}
+
+fn read_u16(packet: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        packet.get(pos..pos + 2)?.try_into().ok()?,
+    ))
+}
+
+/// Reads a possibly compressed name at `pos`, returning it and the position
+/// just after it.
+fn read_name(packet: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut end = None;
+    let mut jumps = 0;
+    loop {
+        let len = usize::from(*packet.get(pos)?);
+        if len & 0xc0 == 0xc0 {
+            end.get_or_insert(pos + 2);
+            jumps += 1;
+            if jumps > 16 {
+                return None;
+            }
+            pos = ((len & 0x3f) << 8) | usize::from(*packet.get(pos + 1)?);
+        } else if len == 0 {
+            return Some((labels.join("."), end.unwrap_or(pos + 1)));
+        } else {
+            let label = packet.get(pos + 1..pos + 1 + len)?;
+            labels.push(String::from_utf8_lossy(label).into_owned());
+            pos += 1 + len;
+        }
+    }
+}
+
+fn parse_response(packet: &[u8]) -> Result<Vec<Record>, String> {
+    let malformed = || String::from("malformed DNS answer");
+    match packet[3] & 0x0f {
+        0 => {}
+        2 => return Err(String::from("DNS server failure (SERVFAIL)")),
+        3 => return Err(String::from("no such name (NXDOMAIN)")),
+        5 => return Err(String::from("DNS query refused (REFUSED)")),
+        code => return Err(format!("DNS error code {}", code)),
+    }
+    let questions = read_u16(packet, 4).ok_or_else(malformed)?;
+    let answers = read_u16(packet, 6).ok_or_else(malformed)?;
+
+    let mut pos = 12;
+    for _ in 0..questions {
+        let (_, next) = read_name(packet, pos).ok_or_else(malformed)?;
+        pos = next + 4;
+    }
+
+    let mut records = Vec::new();
+    for _ in 0..answers {
+        let (_, next) = read_name(packet, pos).ok_or_else(malformed)?;
+        let header = packet.get(next..next + 10).ok_or_else(malformed)?;
+        let rtype = u16::from_be_bytes([header[0], header[1]]);
+        let ttl = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        let start = next + 10;
+        let rdata = packet
+            .get(start..start + usize::from(u16::from_be_bytes([header[8], header[9]])))
+            .ok_or_else(malformed)?;
+        let data = match (rtype, rdata.len()) {
+            (TYPE_A, 4) => Data::Address(IpAddr::V4(Ipv4Addr::new(
+                rdata[0], rdata[1], rdata[2], rdata[3],
+            ))),
+            (TYPE_AAAA, 16) => {
+                let octets: [u8; 16] = rdata.try_into().map_err(|_| malformed())?;
+                Data::Address(IpAddr::V6(Ipv6Addr::from(octets)))
+            }
+            (TYPE_PTR, _) => Data::Name(read_name(packet, start).ok_or_else(malformed)?.0),
+            _ => Data::Other,
+        };
+        records.push(Record { ttl, data });
+        pos = start + rdata.len();
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// An answer to `query` with the given RCODE and answers, each owner
+    /// name a pointer back to the question.
+    fn response(
+        query: &[u8],
+        truncated: bool,
+        rcode: u8,
+        answers: &[(u16, u32, &[u8])],
+    ) -> Vec<u8> {
+        let mut packet = query[..2].to_vec();
+        packet.push(if truncated { 0x83 } else { 0x81 });
+        packet.push(0x80 | rcode);
+        packet.extend_from_slice(&[0, 1]);
+        packet.extend_from_slice(&(answers.len() as u16).to_be_bytes());
+        packet.extend_from_slice(&[0, 0, 0, 0]);
+        packet.extend_from_slice(&query[12..]);
+        for (rtype, ttl, rdata) in answers {
+            packet.extend_from_slice(&[0xc0, 0x0c]);
+            packet.extend_from_slice(&rtype.to_be_bytes());
+            packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+            packet.extend_from_slice(&ttl.to_be_bytes());
+            packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            packet.extend_from_slice(rdata);
+        }
+        packet
+    }
+
+    fn query_type(query: &[u8]) -> u16 {
+        read_u16(query, query.len() - 4).unwrap()
+    }
+
+    /// Answers `count` UDP queries with `answer`.
+    fn udp_stub(socket: UdpSocket, count: usize, answer: fn(&[u8]) -> Vec<u8>) {
+        thread::spawn(move || {
+            let mut buffer = [0u8; 512];
+            for _ in 0..count {
+                let (len, peer) = socket.recv_from(&mut buffer).unwrap();
+                socket.send_to(&answer(&buffer[..len]), peer).unwrap();
+            }
+        });
+    }
+
+    #[test]
+    fn looks_up_addresses_with_lowest_ttl() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server = socket.local_addr().unwrap();
+        udp_stub(socket, 2, |query| match query_type(query) {
+            TYPE_A => response(
+                query,
+                false,
+                0,
+                &[(TYPE_A, 300, &[10, 0, 0, 1]), (TYPE_A, 120, &[10, 0, 0, 2])],
+            ),
+            _ => response(
+                query,
+                false,
+                0,
+                &[(TYPE_AAAA, 600, &Ipv6Addr::LOCALHOST.octets())],
+            ),
+        });
+
+        let (addrs, ttl) = lookup(server, "scan.example.com").unwrap();
+        assert_eq!(
+            addrs,
+            vec![
+                IpAddr::from([10, 0, 0, 1]),
+                IpAddr::from([10, 0, 0, 2]),
+                IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ]
+        );
+        assert_eq!(ttl, Duration::from_secs(120));
+    }
+
+    #[test]
+    fn retries_truncated_answers_over_tcp() {
+        // The TCP listener has to share the UDP socket's port.
+        let (socket, listener) = (0..10)
+            .find_map(|_| {
+                let socket = UdpSocket::bind("127.0.0.1:0").ok()?;
+                let listener = TcpListener::bind(socket.local_addr().ok()?).ok()?;
+                Some((socket, listener))
+            })
+            .unwrap();
+        let server = socket.local_addr().unwrap();
+        udp_stub(socket, 1, |query| response(query, true, 0, &[]));
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut len = [0u8; 2];
+            stream.read_exact(&mut len).unwrap();
+            let mut query = vec![0u8; usize::from(u16::from_be_bytes(len))];
+            stream.read_exact(&mut query).unwrap();
+            let answer = response(&query, false, 0, &[(TYPE_A, 60, &[192, 0, 2, 7])]);
+            let mut message = (answer.len() as u16).to_be_bytes().to_vec();
+            message.extend_from_slice(&answer);
+            stream.write_all(&message).unwrap();
+        });
+
+        let records = query(server, "big.example.com", TYPE_A).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].ttl, 60);
+        assert!(matches!(
+            records[0].data,
+            Data::Address(ip) if ip == IpAddr::from([192, 0, 2, 7])
+        ));
+    }
+
+    #[test]
+    fn reads_compressed_names() {
+        let query = encode_query(7, "7.2.0.192.in-addr.arpa", TYPE_PTR).unwrap();
+        // host.example.com, then www.example.com pointing into it.
+        let mut rdata = vec![4];
+        rdata.extend_from_slice(b"host");
+        rdata.extend_from_slice(&[7]);
+        rdata.extend_from_slice(b"example");
+        rdata.extend_from_slice(&[3]);
+        rdata.extend_from_slice(b"com");
+        rdata.push(0);
+        let packet = response(&query, false, 0, &[(TYPE_PTR, 30, &rdata)]);
+        let host = packet.len() - rdata.len();
+
+        let records = parse_response(&packet).unwrap();
+        assert!(matches!(&records[0].data, Data::Name(name) if name == "host.example.com"));
+
+        let mut packet = packet;
+        let www = packet.len();
+        packet.extend_from_slice(&[3, b'w', b'w', b'w', 0xc0, (host + 5) as u8]);
+        assert_eq!(
+            read_name(&packet, www),
+            Some((String::from("www.example.com"), www + 6))
+        );
+        assert_eq!(
+            read_name(&packet, 12),
+            Some((String::from("7.2.0.192.in-addr.arpa"), query.len() - 4))
+        );
+
+        // A pointer to itself must not loop forever.
+        let looped = packet.len();
+        packet.extend_from_slice(&[0xc0, looped as u8]);
+        assert_eq!(read_name(&packet, looped), None);
+    }
+
+    #[test]
+    fn reports_error_codes() {
+        let query = encode_query(9, "missing.example.com", TYPE_A).unwrap();
+        assert_eq!(
+            parse_response(&response(&query, false, 3, &[])).err(),
+            Some(String::from("no such name (NXDOMAIN)"))
+        );
+        assert_eq!(
+            parse_response(&response(&query, false, 2, &[])).err(),
+            Some(String::from("DNS server failure (SERVFAIL)"))
+        );
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server = socket.local_addr().unwrap();
+        udp_stub(socket, 2, |query| response(query, false, 3, &[]));
+        assert_eq!(
+            lookup(server, "missing.example.com").err(),
+            Some(String::from("no such name (NXDOMAIN)"))
+        );
+    }
+}
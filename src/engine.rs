@@ -0,0 +1,483 @@
+use crate::proxy;
+use crate::resolve::{self, Resolution};
+use crate::trace;
+use std::io;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// The address to connect to for `port` on `target`, through the shared
+/// resolution cache so follow-up connections reach the scanned address.
+/// `None` for names only the proxy can resolve.
+pub fn resolve_addr(target: &str, port: u16) -> Option<SocketAddr> {
+    resolve::resolve(target)
+        .ok()
+        .filter(|resolution| resolution.source != resolve::Source::Proxy)
+        .map(|resolution| resolution.socket_addr(port))
+}
+
+pub fn connect_timeout() -> Duration {
+    std::env::var("VONOGS_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(Duration::from_millis(700))
+}
+
+/// Host-level failures, without a sign of life in between, after which the
+/// rest of a host is skipped.
+pub const HOST_FAILURE_LIMIT: usize = 3;
+
+/// Timeouts in a row, before the host ever answered, after which it counts
+/// as silently dropping every probe.
+pub const HOST_TIMEOUT_LIMIT: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PortStatus {
+    Open,
+    Closed,
+    TimeoutFiltered,
+    /// No route to the host, e.g. nothing answered ARP on the local network.
+    HostUnreachable,
+    /// No route to the host's network.
+    NetworkUnreachable,
+    /// The local system refused to make the connection, e.g. a firewall rule.
+    PermissionDenied,
+    /// Out of file descriptors, sockets or buffer space on this machine.
+    ResourceExhausted,
+    InvalidAddress,
+    /// The `--proxy` could not be used, so the port was never tried.
+    ProxyError,
+}
+
+impl PortStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PortStatus::Open => "OPEN",
+            PortStatus::Closed => "CLOSED",
+            PortStatus::TimeoutFiltered => "TIMEOUT/FILTERED",
+            PortStatus::HostUnreachable => "HOST UNREACHABLE",
+            PortStatus::NetworkUnreachable => "NETWORK UNREACHABLE",
+            PortStatus::PermissionDenied => "PERMISSION DENIED",
+            PortStatus::ResourceExhausted => "RESOURCE EXHAUSTED",
+            PortStatus::InvalidAddress => "Invalid address",
+            PortStatus::ProxyError => "PROXY ERROR",
+        }
+    }
+
+    /// True when the outcome is about the host rather than the port, so
+    /// the remaining ports would fail the same way.
+    pub fn is_host_failure(&self) -> bool {
+        matches!(
+            self,
+            PortStatus::HostUnreachable | PortStatus::NetworkUnreachable | PortStatus::ProxyError
+        )
+    }
+
+    /// An answer from the host itself, which proves it is reachable.
+    fn is_sign_of_life(&self) -> bool {
+        matches!(self, PortStatus::Open | PortStatus::Closed)
+    }
+}
+
+/// Counts host-level failures since the host last answered, to decide when
+/// to stop probing it instead of waiting out every remaining port. Timeouts
+/// do not reset the count: while ARP fails, Linux reports some probes as
+/// timed out and others as unreachable. Until the host first answers, its
+/// timeouts in a row are counted too, since a host that drops everything
+/// would otherwise cost the full timeout on every port.
+#[derive(Debug, Default)]
+pub struct HostWatch {
+    failures: usize,
+    timeouts: usize,
+    answered: bool,
+}
+
+impl HostWatch {
+    /// Returns true once `HOST_FAILURE_LIMIT` host failures came in without
+    /// an answer in between, or the first `HOST_TIMEOUT_LIMIT` results in a
+    /// row timed out.
+    pub fn give_up(&mut self, status: PortStatus) -> bool {
+        if status.is_sign_of_life() {
+            self.failures = 0;
+            self.answered = true;
+        } else if status.is_host_failure() {
+            self.failures += 1;
+        }
+        if status == PortStatus::TimeoutFiltered && !self.answered {
+            self.timeouts += 1;
+        } else {
+            self.timeouts = 0;
+        }
+        self.failures >= HOST_FAILURE_LIMIT || self.timeouts >= HOST_TIMEOUT_LIMIT
+    }
+}
+
+/// The host-level failure that ended a scan early, if `results` stops short
+/// of `total` ports because of one.
+pub fn host_abort(results: &[PortResult], total: usize) -> Option<PortStatus> {
+    let last = results.last()?;
+    if results.len() >= total {
+        return None;
+    }
+    let mut watch = HostWatch::default();
+    results
+        .iter()
+        .any(|r| watch.give_up(r.status))
+        .then_some(last.status)
+}
+
+/// `host unreachable after 3 of 1024 ports, rest skipped`, or `no answer
+/// after 10 of 1024 ports, rest skipped` for a host that timed out.
+pub fn abort_note(status: PortStatus, scanned: usize, total: usize) -> String {
+    let cause = match status {
+        PortStatus::TimeoutFiltered => String::from("no answer"),
+        status => status.label().to_lowercase(),
+    };
+    format!(
+        "{} after {} of {} ports, rest skipped",
+        cause, scanned, total
+    )
+}
+
+/// Why a probe ended the way it did, taken from the connect error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reason {
+    Connected,
+    Refused,
+    TimedOut,
+    HostUnreachable,
+    NetworkUnreachable,
+    PermissionDenied,
+    ResourceExhausted,
+    Reset,
+    InvalidAddress,
+    /// A SOCKS5 reply code other than success.
+    Socks(u8),
+    /// A non-2xx status from an HTTP proxy's CONNECT.
+    Http(u16),
+    /// The proxy hung up instead of answering the CONNECT.
+    ProxyClosed,
+    /// Why the proxy itself could not be used.
+    Proxy(&'static str),
+    Other(io::ErrorKind),
+}
+
+/// `EMFILE`, `ENFILE` and `ENOBUFS`: no file descriptor, socket or buffer
+/// left on this machine. `std` has no stable `ErrorKind` for them.
+#[cfg(target_os = "linux")]
+const EXHAUSTED_OS_ERRORS: [i32; 3] = [24, 23, 105];
+#[cfg(all(unix, not(target_os = "linux")))]
+const EXHAUSTED_OS_ERRORS: [i32; 3] = [24, 23, 55];
+#[cfg(windows)]
+const EXHAUSTED_OS_ERRORS: [i32; 2] = [10024, 10055];
+#[cfg(not(any(unix, windows)))]
+const EXHAUSTED_OS_ERRORS: [i32; 0] = [];
+
+impl Reason {
+    fn from_error(error: &io::Error) -> Reason {
+        if error
+            .raw_os_error()
+            .is_some_and(|code| EXHAUSTED_OS_ERRORS.contains(&code))
+        {
+            return Reason::ResourceExhausted;
+        }
+        match error.kind() {
+            io::ErrorKind::ConnectionRefused => Reason::Refused,
+            io::ErrorKind::TimedOut => Reason::TimedOut,
+            io::ErrorKind::HostUnreachable => Reason::HostUnreachable,
+            io::ErrorKind::NetworkUnreachable => Reason::NetworkUnreachable,
+            io::ErrorKind::PermissionDenied => Reason::PermissionDenied,
+            io::ErrorKind::ConnectionReset => Reason::Reset,
+            // The address itself cannot be connected to, such as an IPv6
+            // link-local address without a scope.
+            io::ErrorKind::InvalidInput => Reason::InvalidAddress,
+            // connect() only reports this when no local port is left.
+            io::ErrorKind::AddrNotAvailable | io::ErrorKind::OutOfMemory => {
+                Reason::ResourceExhausted
+            }
+            kind => Reason::Other(kind),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Reason::Connected => String::from("connected"),
+            Reason::Refused => String::from("connection refused"),
+            Reason::TimedOut => String::from("timed out"),
+            Reason::HostUnreachable => String::from("host unreachable"),
+            Reason::NetworkUnreachable => String::from("network unreachable"),
+            Reason::PermissionDenied => String::from("permission denied"),
+            Reason::ResourceExhausted => String::from("out of sockets or file descriptors"),
+            Reason::Reset => String::from("connection reset"),
+            Reason::InvalidAddress => String::from("invalid address"),
+            Reason::Socks(code) => format!("SOCKS {:#04x} {}", code, socks_reply(*code)),
+            Reason::Http(status) => format!("HTTP {} {}", status, http_reason(*status))
+                .trim_end()
+                .to_string(),
+            Reason::ProxyClosed => String::from("proxy closed the connection"),
+            Reason::Proxy(message) => message.to_string(),
+            Reason::Other(kind) => format!("{:?}", kind),
+        }
+    }
+
+    /// The port state this outcome stands for.
+    pub fn status(&self) -> PortStatus {
+        match self {
+            Reason::Connected => PortStatus::Open,
+            Reason::TimedOut => PortStatus::TimeoutFiltered,
+            Reason::HostUnreachable => PortStatus::HostUnreachable,
+            Reason::NetworkUnreachable => PortStatus::NetworkUnreachable,
+            Reason::PermissionDenied => PortStatus::PermissionDenied,
+            Reason::ResourceExhausted => PortStatus::ResourceExhausted,
+            Reason::InvalidAddress => PortStatus::InvalidAddress,
+            Reason::Socks(0x02) => PortStatus::PermissionDenied,
+            Reason::Socks(0x03) => PortStatus::NetworkUnreachable,
+            Reason::Socks(0x04) => PortStatus::HostUnreachable,
+            Reason::Socks(0x06) => PortStatus::TimeoutFiltered,
+            Reason::Socks(0x01 | 0x05) => PortStatus::Closed,
+            Reason::Http(403) => PortStatus::PermissionDenied,
+            Reason::Http(502 | 503) => PortStatus::Closed,
+            Reason::Http(504) => PortStatus::TimeoutFiltered,
+            Reason::Socks(_) | Reason::Http(_) | Reason::Proxy(_) => PortStatus::ProxyError,
+            Reason::Refused | Reason::Reset | Reason::ProxyClosed | Reason::Other(_) => {
+                PortStatus::Closed
+            }
+        }
+    }
+
+    /// Outcomes reported by a proxy, worth showing next to any state.
+    fn is_proxy_reply(&self) -> bool {
+        matches!(
+            self,
+            Reason::Socks(_) | Reason::Http(_) | Reason::ProxyClosed | Reason::Proxy(_)
+        )
+    }
+}
+
+/// The reason phrase of the HTTP statuses proxies answer CONNECT with.
+fn http_reason(status: u16) -> &'static str {
+    match status {
+        400 => "Bad Request",
+        403 => "Forbidden",
+        405 => "Method Not Allowed",
+        407 => "Proxy Authentication Required",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ => "",
+    }
+}
+
+/// The RFC 1928 meaning of a SOCKS5 reply code.
+fn socks_reply(code: u8) -> &'static str {
+    match code {
+        0x01 => "general failure",
+        0x02 => "not allowed by ruleset",
+        0x03 => "network unreachable",
+        0x04 => "host unreachable",
+        0x05 => "connection refused",
+        0x06 => "TTL expired",
+        0x07 => "command not supported",
+        0x08 => "address type not supported",
+        _ => "unassigned",
+    }
+}
+
+/// Connects once to the resolved address of `target`, directly or through
+/// the `--proxy`, keeping the reason and tracing the probe at `-v` and
+/// `-vv`.
+pub fn probe_port(target: &Resolution, port: u16) -> PortResult {
+    let socket_addr = target.socket_addr(port);
+    let via = proxy::current()
+        .map(|proxy| format!(" via {}", proxy.label()))
+        .unwrap_or_default();
+    trace::log(
+        2,
+        &format!(
+            "[vv] connecting to {} ({}){} with a {} ms timeout",
+            socket_addr,
+            target.target,
+            via,
+            connect_timeout().as_millis()
+        ),
+    );
+
+    let started = Instant::now();
+    let mut error = None;
+    let reason = match proxy::current() {
+        Some(proxy) => match proxy.open(socket_addr, &target.target, connect_timeout()) {
+            Ok(_) => Reason::Connected,
+            Err(failure) => failure.reason(),
+        },
+        None => match TcpStream::connect_timeout(&socket_addr, connect_timeout()) {
+            Ok(_) => Reason::Connected,
+            Err(e) => {
+                let reason = Reason::from_error(&e);
+                error = Some(e);
+                reason
+            }
+        },
+    };
+    let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+    let status = reason.status();
+
+    trace::log(
+        1,
+        &format!(
+            "[v] {} ({}): {}, {} in {:.1} ms",
+            socket_addr,
+            target.target,
+            status.label(),
+            reason.label(),
+            elapsed_ms
+        ),
+    );
+    if let Some(e) = &error {
+        trace::log(
+            2,
+            &format!("[vv] {} error kind {:?}: {}", socket_addr, e.kind(), e),
+        );
+    }
+
+    PortResult {
+        port,
+        status,
+        reason,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PortResult {
+    pub port: u16,
+    pub status: PortStatus,
+    pub reason: Reason,
+}
+
+impl PortResult {
+    /// The status as written to logs; closed ports and proxy replies say
+    /// why.
+    pub fn describe(&self) -> String {
+        if self.status == PortStatus::Closed || self.reason.is_proxy_reply() {
+            format!("{} ({})", self.status.label(), self.reason.label())
+        } else {
+            self.status.label().to_string()
+        }
+    }
+}
+
+/// Scans `ports` in order on the address `target` resolved to, calling
+/// `on_result` after each probe so callers can report progress.
+pub fn scan_ports(
+    target: &Resolution,
+    ports: &[u16],
+    on_result: impl FnMut(usize, &PortResult),
+) -> Vec<PortResult> {
+    scan_ports_until(target, ports, &AtomicBool::new(false), on_result)
+}
+
+/// Like `scan_ports`, but stops before the next probe once `cancel` is set.
+/// Both stop early when the host itself is unreachable; see `host_abort`.
+pub fn scan_ports_until(
+    target: &Resolution,
+    ports: &[u16],
+    cancel: &AtomicBool,
+    mut on_result: impl FnMut(usize, &PortResult),
+) -> Vec<PortResult> {
+    let mut results = Vec::with_capacity(ports.len());
+    let mut watch = HostWatch::default();
+    for (index, port) in ports.iter().enumerate() {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let result = probe_port(target, *port);
+        on_result(index, &result);
+        results.push(result);
+        if watch.give_up(result.status) {
+            break;
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(statuses: &[PortStatus]) -> Vec<PortResult> {
+        statuses
+            .iter()
+            .enumerate()
+            .map(|(i, status)| PortResult {
+                port: i as u16 + 1,
+                status: *status,
+                reason: Reason::TimedOut,
+            })
+            .collect()
+    }
+
+    fn first_give_up(statuses: &[PortStatus]) -> Option<usize> {
+        let mut watch = HostWatch::default();
+        statuses.iter().position(|status| watch.give_up(*status))
+    }
+
+    #[test]
+    fn gives_up_after_host_failures_without_an_answer() {
+        use PortStatus::*;
+        assert_eq!(
+            first_give_up(&[
+                HostUnreachable,
+                TimeoutFiltered,
+                HostUnreachable,
+                ProxyError
+            ]),
+            Some(3)
+        );
+        assert_eq!(
+            first_give_up(&[HostUnreachable, HostUnreachable, Closed, NetworkUnreachable]),
+            None
+        );
+    }
+
+    #[test]
+    fn gives_up_on_a_host_that_never_answers() {
+        use PortStatus::*;
+        let silent = [TimeoutFiltered; HOST_TIMEOUT_LIMIT];
+        assert_eq!(first_give_up(&silent), Some(HOST_TIMEOUT_LIMIT - 1));
+
+        // A host that answered once is up, however many ports it filters.
+        let mut filtered = vec![Closed];
+        filtered.extend([TimeoutFiltered; 50]);
+        assert_eq!(first_give_up(&filtered), None);
+
+        // Anything else between timeouts starts the count again.
+        let mut interrupted = vec![TimeoutFiltered; HOST_TIMEOUT_LIMIT - 1];
+        interrupted.push(PermissionDenied);
+        interrupted.extend([TimeoutFiltered; HOST_TIMEOUT_LIMIT - 1]);
+        assert_eq!(first_give_up(&interrupted), None);
+    }
+
+    #[test]
+    fn reports_why_a_host_was_abandoned() {
+        use PortStatus::*;
+        let unreachable = results(&[HostUnreachable; HOST_FAILURE_LIMIT]);
+        assert_eq!(host_abort(&unreachable, 1024), Some(HostUnreachable));
+        assert_eq!(host_abort(&unreachable, HOST_FAILURE_LIMIT), None);
+
+        let silent = results(&[TimeoutFiltered; HOST_TIMEOUT_LIMIT]);
+        assert_eq!(host_abort(&silent, 1024), Some(TimeoutFiltered));
+        assert_eq!(
+            abort_note(TimeoutFiltered, HOST_TIMEOUT_LIMIT, 1024),
+            "no answer after 10 of 1024 ports, rest skipped"
+        );
+        assert_eq!(
+            abort_note(HostUnreachable, 3, 1024),
+            "host unreachable after 3 of 1024 ports, rest skipped"
+        );
+
+        // Stopped short for another reason, such as a cancelled scan.
+        assert_eq!(host_abort(&results(&[Closed, TimeoutFiltered]), 1024), None);
+        assert_eq!(host_abort(&[], 1024), None);
+    }
+}
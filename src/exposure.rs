@@ -0,0 +1,360 @@
+use crate::findings::{Finding, Severity};
+use crate::http_probe::http_get;
+use crate::proxy;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+const MONGO_OP_MSG: i32 = 2013;
+
+#[derive(Debug, Clone, Copy)]
+enum Service {
+    Redis,
+    Memcached,
+    MongoDb,
+    Elasticsearch,
+    CouchDb,
+}
+
+impl Service {
+    fn for_port(port: u16) -> Option<Service> {
+        match port {
+            6379 => Some(Service::Redis),
+            11211 => Some(Service::Memcached),
+            27017 => Some(Service::MongoDb),
+            9200 => Some(Service::Elasticsearch),
+            5984 => Some(Service::CouchDb),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Service::Redis => "Redis",
+            Service::Memcached => "Memcached",
+            Service::MongoDb => "MongoDB",
+            Service::Elasticsearch => "Elasticsearch",
+            Service::CouchDb => "CouchDB",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Access {
+    OpenWithoutAuth,
+    AuthRequired,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExposureInfo {
+    pub service: &'static str,
+    pub access: Access,
+    pub detail: Option<String>,
+}
+
+impl ExposureInfo {
+    pub fn describe(&self) -> String {
+        let access = match self.access {
+            Access::OpenWithoutAuth => "open without authentication",
+            Access::AuthRequired => "authentication required",
+        };
+        match &self.detail {
+            Some(detail) => format!("{} {} ({})", self.service, access, detail),
+            None => format!("{} {}", self.service, access),
+        }
+    }
+
+    pub fn finding(&self, port: u16) -> Option<Finding> {
+        if self.access != Access::OpenWithoutAuth {
+            return None;
+        }
+        Some(Finding {
+            rule_id: format!("{}-no-auth", self.service.to_lowercase()),
+            port,
+            severity: Severity::High,
+            title: format!("{} open without authentication", self.service),
+            description: format!(
+                "{} on port {} answered a read-only request without credentials; \
+                 anyone who can reach it can read (and likely modify) its data.",
+                self.service, port
+            ),
+            remediation: format!(
+                "Enable authentication on {} and bind it to trusted interfaces or \
+                 restrict the port with a firewall.",
+                self.service
+            ),
+        })
+    }
+}
+
+pub fn is_exposure_port(port: u16) -> bool {
+    Service::for_port(port).is_some()
+}
+
+/// Runs a safe, read-only request against a database or cache to see
+/// whether it answers without credentials.
+pub fn check_exposure(
+    addr: SocketAddr,
+    host: &str,
+    timeout: Duration,
+) -> Result<ExposureInfo, String> {
+    let service = Service::for_port(addr.port()).ok_or("no exposure check for this port")?;
+    let (access, detail) = match service {
+        Service::Redis => check_redis(addr, host, timeout)?,
+        Service::Memcached => check_memcached(addr, host, timeout)?,
+        Service::MongoDb => check_mongodb(addr, host, timeout)?,
+        Service::Elasticsearch => check_http_service(addr, host, "/", "\"number\"", timeout)?,
+        Service::CouchDb => check_http_service(addr, host, "/_all_dbs", "", timeout)?,
+    };
+
+    Ok(ExposureInfo {
+        service: service.name(),
+        access,
+        detail,
+    })
+}
+
+fn connect(addr: SocketAddr, host: &str, timeout: Duration) -> Result<TcpStream, String> {
+    let stream =
+        proxy::connect(addr, host, timeout).map_err(|e| format!("connect failed: {}", e))?;
+    let io_timeout = timeout.max(Duration::from_secs(3));
+    let _ = stream.set_read_timeout(Some(io_timeout));
+    let _ = stream.set_write_timeout(Some(io_timeout));
+    Ok(stream)
+}
+
+fn exchange(
+    stream: &mut TcpStream,
+    request: &[u8],
+    is_complete: impl Fn(&[u8]) -> bool,
+) -> Result<Vec<u8>, String> {
+    stream
+        .write_all(request)
+        .map_err(|e| format!("write failed: {}", e))?;
+
+    let mut response = Vec::new();
+    let mut buf = [0u8; 4096];
+    while !is_complete(&response) && response.len() < MAX_RESPONSE_BYTES {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => response.extend_from_slice(&buf[..n]),
+            Err(_) if response.is_empty() => return Err(String::from("no response")),
+            Err(_) => break,
+        }
+    }
+    Ok(response)
+}
+
+fn find_field(text: &str, prefix: &str) -> Option<String> {
+    text.lines()
+        .find_map(|line| line.strip_prefix(prefix))
+        .map(|value| value.trim().to_string())
+}
+
+fn check_redis(
+    addr: SocketAddr,
+    host: &str,
+    timeout: Duration,
+) -> Result<(Access, Option<String>), String> {
+    let mut stream = connect(addr, host, timeout)?;
+    let reply = exchange(&mut stream, b"PING\r\n", |r| r.ends_with(b"\r\n"))?;
+    let reply = String::from_utf8_lossy(&reply);
+    if reply.starts_with("-NOAUTH") || reply.starts_with("-ERR operation not permitted") {
+        return Ok((Access::AuthRequired, None));
+    }
+    if !reply.starts_with("+PONG") {
+        return Err(String::from("unexpected Redis reply"));
+    }
+
+    let info = exchange(&mut stream, b"INFO server\r\n", |r| {
+        (r.windows(13).any(|w| w == b"redis_version") && r.ends_with(b"\r\n\r\n"))
+            || r.starts_with(b"-")
+    })?;
+    let detail = find_field(&String::from_utf8_lossy(&info), "redis_version:")
+        .map(|version| format!("version {}", version));
+    Ok((Access::OpenWithoutAuth, detail))
+}
+
+fn check_memcached(
+    addr: SocketAddr,
+    host: &str,
+    timeout: Duration,
+) -> Result<(Access, Option<String>), String> {
+    let mut stream = connect(addr, host, timeout)?;
+    let reply = exchange(&mut stream, b"stats\r\n", |r| {
+        r.ends_with(b"END\r\n") || r.ends_with(b"ERROR\r\n")
+    })?;
+    let reply = String::from_utf8_lossy(&reply);
+    if !reply.starts_with("STAT ") {
+        // SASL-enabled servers reject plain text commands.
+        return Ok((Access::AuthRequired, None));
+    }
+    let detail = find_field(&reply, "STAT version ").map(|version| format!("version {}", version));
+    Ok((Access::OpenWithoutAuth, detail))
+}
+
+fn check_http_service(
+    addr: SocketAddr,
+    host: &str,
+    path: &str,
+    version_key: &str,
+    timeout: Duration,
+) -> Result<(Access, Option<String>), String> {
+    let (info, body) = http_get(addr, host, path, timeout).ok_or("no HTTP response")?;
+    match info.status_code {
+        200 => {
+            let detail = if version_key.is_empty() {
+                None
+            } else {
+                json_string_value(&body, version_key).map(|version| format!("version {}", version))
+            };
+            Ok((Access::OpenWithoutAuth, detail))
+        }
+        401 | 403 => Ok((Access::AuthRequired, None)),
+        other => Err(format!("unexpected HTTP status {}", other)),
+    }
+}
+
+/// Pulls the string value following `key` out of a JSON body without a full parser.
+fn json_string_value(body: &str, key: &str) -> Option<String> {
+    let after_key = &body[body.find(key)? + key.len()..];
+    let after_colon = after_key.trim_start().strip_prefix(':')?.trim_start();
+    let value = after_colon.strip_prefix('"')?;
+    Some(value[..value.find('"')?].to_string())
+}
+
+fn check_mongodb(
+    addr: SocketAddr,
+    host: &str,
+    timeout: Duration,
+) -> Result<(Access, Option<String>), String> {
+    let mut stream = connect(addr, host, timeout)?;
+
+    // `hello` only exists since MongoDB 4.4.2; older servers reject it, or
+    // drop the connection, but still answer the legacy `isMaster`.
+    let greeted = match mongo_command(&mut stream, 1, "hello") {
+        Ok(hello) => bson_number(&hello, "ok") == Some(1.0),
+        Err(_) => {
+            stream = connect(addr, host, timeout)?;
+            false
+        }
+    };
+    if !greeted {
+        let is_master = mongo_command(&mut stream, 2, "isMaster")?;
+        if bson_number(&is_master, "ok") != Some(1.0) {
+            return Err(String::from("unexpected MongoDB reply"));
+        }
+    }
+    let build_info = mongo_command(&mut stream, 3, "buildInfo")?;
+    let detail = bson_string(&build_info, "version").map(|version| format!("version {}", version));
+
+    // listDatabases is read-only but, unlike hello and isMaster, requires
+    // credentials when access control is enabled.
+    let databases = mongo_command(&mut stream, 4, "listDatabases")?;
+    let access = if bson_number(&databases, "ok") == Some(1.0) {
+        Access::OpenWithoutAuth
+    } else {
+        Access::AuthRequired
+    };
+    Ok((access, detail))
+}
+
+fn mongo_command(
+    stream: &mut TcpStream,
+    request_id: i32,
+    command: &str,
+) -> Result<Vec<u8>, String> {
+    let mut document = Vec::new();
+    document.push(0x10); // int32
+    document.extend_from_slice(command.as_bytes());
+    document.push(0);
+    document.extend_from_slice(&1i32.to_le_bytes());
+    document.push(0x02); // string
+    document.extend_from_slice(b"$db\0");
+    document.extend_from_slice(&6i32.to_le_bytes());
+    document.extend_from_slice(b"admin\0");
+    document.push(0);
+    let document_len = (document.len() + 4) as i32;
+
+    let mut message = Vec::new();
+    let message_len = 16 + 4 + 1 + document_len;
+    message.extend_from_slice(&message_len.to_le_bytes());
+    message.extend_from_slice(&request_id.to_le_bytes());
+    message.extend_from_slice(&0i32.to_le_bytes());
+    message.extend_from_slice(&MONGO_OP_MSG.to_le_bytes());
+    message.extend_from_slice(&0u32.to_le_bytes());
+    message.push(0); // body section
+    message.extend_from_slice(&document_len.to_le_bytes());
+    message.extend_from_slice(&document);
+
+    // A negative length can never be reached, so stop reading and let the
+    // document checks reject the reply.
+    let reply = exchange(stream, &message, |r| {
+        r.len() >= 4
+            && usize::try_from(i32::from_le_bytes([r[0], r[1], r[2], r[3]]))
+                .is_ok_and(|len| r.len() >= len)
+    })?;
+    // Header (16), flag bits (4) and section kind (1) precede the document.
+    reply
+        .get(21..)
+        .map(|doc| doc.to_vec())
+        .ok_or_else(|| String::from("truncated MongoDB reply"))
+}
+
+/// Walks the top-level elements of a BSON document. Lengths come from the
+/// server, so negative ones and ones that run past the end end the walk.
+fn bson_element<'a>(document: &'a [u8], wanted: &str) -> Option<(u8, &'a [u8])> {
+    let mut pos = 4;
+    while pos < document.len() {
+        let element_type = document[pos];
+        if element_type == 0 {
+            return None;
+        }
+        let name_end = pos + 1 + document.get(pos + 1..)?.iter().position(|b| *b == 0)?;
+        let name = &document[pos + 1..name_end];
+        let value_start = name_end + 1;
+        let read_i32 = |at: usize| -> Option<usize> {
+            let bytes = document.get(at..at.checked_add(4)?)?;
+            usize::try_from(i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])).ok()
+        };
+        let value_len = match element_type {
+            0x01 | 0x09 | 0x11 | 0x12 => 8,
+            0x02 | 0x0d | 0x0e => read_i32(value_start)?.checked_add(4)?,
+            0x03 | 0x04 => read_i32(value_start)?,
+            0x05 => read_i32(value_start)?.checked_add(5)?,
+            0x07 => 12,
+            0x08 => 1,
+            0x0a | 0xff | 0x7f => 0,
+            0x10 => 4,
+            0x13 => 16,
+            _ => return None,
+        };
+        let value_end = value_start.checked_add(value_len)?;
+        let value = document.get(value_start..value_end)?;
+        if name == wanted.as_bytes() {
+            return Some((element_type, value));
+        }
+        pos = value_end;
+    }
+    None
+}
+
+fn bson_number(document: &[u8], name: &str) -> Option<f64> {
+    match bson_element(document, name)? {
+        (0x01, value) => Some(f64::from_le_bytes(value.try_into().ok()?)),
+        (0x10, value) => Some(i32::from_le_bytes(value.try_into().ok()?) as f64),
+        (0x12, value) => Some(i64::from_le_bytes(value.try_into().ok()?) as f64),
+        _ => None,
+    }
+}
+
+fn bson_string(document: &[u8], name: &str) -> Option<String> {
+    match bson_element(document, name)? {
+        (0x02, value) => {
+            let text = value.get(4..value.len().saturating_sub(1))?;
+            Some(String::from_utf8_lossy(text).to_string())
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,448 @@
+use crate::json;
+use crate::services::get_service_name;
+use std::fs;
+
+pub const DEFAULT_RULES_FILE: &str = "vonogs_rules.conf";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Low => "LOW",
+            Severity::Medium => "MEDIUM",
+            Severity::High => "HIGH",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Severity> {
+        match value.trim().to_lowercase().as_str() {
+            "info" => Some(Severity::Info),
+            "low" => Some(Severity::Low),
+            "medium" => Some(Severity::Medium),
+            "high" => Some(Severity::High),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub rule_id: String,
+    pub port: u16,
+    pub severity: Severity,
+    pub title: String,
+    pub description: String,
+    pub remediation: String,
+}
+
+impl Finding {
+    pub fn summary_line(&self) -> String {
+        format!(
+            "[{}] Port {}: {}",
+            self.severity.label(),
+            self.port,
+            self.title
+        )
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"rule_id\":{},\"port\":{},\"severity\":{},\"title\":{},\"description\":{},\"remediation\":{}}}",
+            json::string(&self.rule_id),
+            self.port,
+            json::string(self.severity.label()),
+            json::string(&self.title),
+            json::string(&self.description),
+            json::string(&self.remediation)
+        )
+    }
+}
+
+/// Highest severity first, then by port.
+pub fn sort_findings(findings: &mut [Finding]) {
+    findings.sort_by(|a, b| b.severity.cmp(&a.severity).then(a.port.cmp(&b.port)));
+}
+
+/// A rule that raises a finding when one of its ports or services is open.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub id: String,
+    pub ports: Vec<u16>,
+    pub services: Vec<String>,
+    pub severity: Severity,
+    pub title: String,
+    pub description: String,
+    pub remediation: String,
+    pub enabled: bool,
+}
+
+impl Rule {
+    fn new(id: &str) -> Rule {
+        Rule {
+            id: id.to_string(),
+            ports: Vec::new(),
+            services: Vec::new(),
+            severity: Severity::Medium,
+            title: String::new(),
+            description: String::new(),
+            remediation: String::new(),
+            enabled: true,
+        }
+    }
+
+    fn matches(&self, port: u16) -> bool {
+        let service = get_service_name(port);
+        self.ports.contains(&port)
+            || self
+                .services
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(service))
+    }
+
+    fn finding(&self, port: u16) -> Finding {
+        let fill = |text: &str| {
+            text.replace("{port}", &port.to_string())
+                .replace("{service}", get_service_name(port))
+        };
+        Finding {
+            rule_id: self.id.clone(),
+            port,
+            severity: self.severity,
+            title: fill(&self.title),
+            description: fill(&self.description),
+            remediation: fill(&self.remediation),
+        }
+    }
+}
+
+fn builtin_rule(
+    id: &str,
+    ports: &[u16],
+    severity: Severity,
+    title: &str,
+    description: &str,
+    remediation: &str,
+) -> Rule {
+    Rule {
+        ports: ports.to_vec(),
+        severity,
+        title: title.to_string(),
+        description: description.to_string(),
+        remediation: remediation.to_string(),
+        ..Rule::new(id)
+    }
+}
+
+pub fn builtin_rules() -> Vec<Rule> {
+    vec![
+        builtin_rule(
+            "telnet-exposed",
+            &[23],
+            Severity::High,
+            "Telnet service exposed",
+            "Telnet sends credentials and session data in cleartext.",
+            "Disable Telnet and use SSH for remote administration.",
+        ),
+        builtin_rule(
+            "ftp-exposed",
+            &[21, 2121],
+            Severity::Medium,
+            "FTP service exposed",
+            "FTP transfers credentials and files in cleartext unless upgraded with AUTH TLS.",
+            "Replace FTP with SFTP, or require FTPS and restrict access to trusted hosts.",
+        ),
+        builtin_rule(
+            "smb-exposed",
+            &[445, 139],
+            Severity::High,
+            "SMB file sharing exposed",
+            "SMB is a frequent target for worms and credential relay attacks.",
+            "Block SMB at the network edge and only allow it from trusted internal hosts.",
+        ),
+        builtin_rule(
+            "rdp-exposed",
+            &[3389],
+            Severity::High,
+            "RDP exposed",
+            "Remote Desktop is heavily targeted by brute force and pre-auth exploits.",
+            "Put RDP behind a VPN or gateway and enforce Network Level Authentication.",
+        ),
+        builtin_rule(
+            "vnc-exposed",
+            &[5900, 5901],
+            Severity::High,
+            "VNC exposed",
+            "VNC often uses weak password-only authentication and unencrypted sessions.",
+            "Restrict VNC to trusted networks or tunnel it over SSH or a VPN.",
+        ),
+        builtin_rule(
+            "metasploit-listener",
+            &[4444],
+            Severity::Critical,
+            "Possible Metasploit listener on port 4444",
+            "Port 4444 is the default Metasploit handler port and may indicate a backdoor.",
+            "Identify the listening process immediately and investigate the host for compromise.",
+        ),
+    ]
+}
+
+/// Built-in rules merged with rules from the config file. A config rule with
+/// the same id as a built-in one replaces it; `enabled = false` turns it off.
+pub fn load_rules() -> (Vec<Rule>, Vec<String>) {
+    let mut rules = builtin_rules();
+    let mut warnings = Vec::new();
+
+    let path = std::env::var("VONOGS_RULES_FILE").unwrap_or(DEFAULT_RULES_FILE.to_string());
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(_) => return (rules, warnings),
+    };
+
+    let (custom_rules, parse_warnings) = parse_rules(&contents);
+    warnings.extend(
+        parse_warnings
+            .into_iter()
+            .map(|w| format!("{}: {}", path, w)),
+    );
+
+    merge_rules(&mut rules, custom_rules);
+    (rules, warnings)
+}
+
+/// Replaces rules in `rules` with the `custom` ones of the same id and
+/// appends the rest.
+fn merge_rules(rules: &mut Vec<Rule>, custom: Vec<Rule>) {
+    for rule in custom {
+        match rules.iter_mut().find(|r| r.id == rule.id) {
+            Some(existing) => *existing = rule,
+            None => rules.push(rule),
+        }
+    }
+}
+
+/// Parses `[rule <id>]` sections of `key = value` lines.
+pub fn parse_rules(contents: &str) -> (Vec<Rule>, Vec<String>) {
+    let mut rules: Vec<Rule> = Vec::new();
+    let mut warnings = Vec::new();
+
+    for (index, raw_line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            match header.trim().strip_prefix("rule ") {
+                Some(id) if !id.trim().is_empty() => rules.push(Rule::new(id.trim())),
+                _ => warnings.push(format!("line {}: expected [rule <id>]", line_number)),
+            }
+            continue;
+        }
+
+        let rule = match rules.last_mut() {
+            Some(rule) => rule,
+            None => {
+                warnings.push(format!("line {}: setting outside a rule", line_number));
+                continue;
+            }
+        };
+
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => {
+                warnings.push(format!("line {}: expected key = value", line_number));
+                continue;
+            }
+        };
+
+        match key {
+            "port" | "ports" => {
+                for part in value.split(',') {
+                    match part.trim().parse::<u16>() {
+                        Ok(port) if port != 0 => rule.ports.push(port),
+                        _ => warnings.push(format!(
+                            "line {}: invalid port '{}'",
+                            line_number,
+                            part.trim()
+                        )),
+                    }
+                }
+            }
+            "service" | "services" => rule.services.extend(
+                value
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty()),
+            ),
+            "severity" => match Severity::parse(value) {
+                Some(severity) => rule.severity = severity,
+                None => warnings.push(format!(
+                    "line {}: unknown severity '{}'",
+                    line_number, value
+                )),
+            },
+            "title" => rule.title = value.to_string(),
+            "description" => rule.description = value.to_string(),
+            "remediation" => rule.remediation = value.to_string(),
+            "enabled" => rule.enabled = value != "false" && value != "no",
+            other => warnings.push(format!("line {}: unknown key '{}'", line_number, other)),
+        }
+    }
+
+    rules.retain(|rule| {
+        let usable = !(rule.enabled && rule.ports.is_empty() && rule.services.is_empty());
+        if !usable {
+            warnings.push(format!("rule '{}' has no port or service", rule.id));
+        }
+        usable
+    });
+    for rule in rules.iter_mut().filter(|r| r.title.is_empty()) {
+        rule.title = format!("{} ({{service}} on port {{port}})", rule.id);
+    }
+
+    (rules, warnings)
+}
+
+pub fn evaluate_rules(rules: &[Rule], open_ports: &[u16]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for port in open_ports {
+        for rule in rules.iter().filter(|r| r.enabled && r.matches(*port)) {
+            findings.push(rule.finding(*port));
+        }
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rule_sections() {
+        let (rules, warnings) = parse_rules(
+            "# house rules\n\
+             [rule internal-admin]\n\
+             ports = 8081, 9090\n\
+             services = Redis\n\
+             severity = critical\n\
+             title = Admin UI on {port}\n\
+             description = {service} reachable on port {port}.\n\
+             \n\
+             [rule quiet]\n\
+             port = 7000\n",
+        );
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].id, "internal-admin");
+        assert_eq!(rules[0].ports, vec![8081, 9090]);
+        assert_eq!(rules[0].services, vec!["Redis"]);
+        assert_eq!(rules[0].severity, Severity::Critical);
+        assert_eq!(rules[1].severity, Severity::Medium);
+        assert_eq!(rules[1].title, "quiet ({service} on port {port})");
+
+        let findings = evaluate_rules(&rules, &[22, 6379, 9090]);
+        let ids: Vec<(&str, u16)> = findings
+            .iter()
+            .map(|f| (f.rule_id.as_str(), f.port))
+            .collect();
+        assert_eq!(
+            ids,
+            vec![("internal-admin", 6379), ("internal-admin", 9090)]
+        );
+        assert_eq!(findings[1].title, "Admin UI on 9090");
+    }
+
+    #[test]
+    fn warns_about_bad_lines() {
+        let (rules, warnings) = parse_rules(
+            "severity = high\n\
+             [rule]\n\
+             [rule a]\n\
+             ports 22\n\
+             ports = 22, 0, 65536, ssh\n\
+             severity = urgent\n\
+             colour = red\n\
+             [rule empty]\n\
+             title = Nothing to match\n\
+             [rule off]\n\
+             enabled = no\n",
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                "line 1: setting outside a rule",
+                "line 2: expected [rule <id>]",
+                "line 4: expected key = value",
+                "line 5: invalid port '0'",
+                "line 5: invalid port '65536'",
+                "line 5: invalid port 'ssh'",
+                "line 6: unknown severity 'urgent'",
+                "line 7: unknown key 'colour'",
+                "rule 'empty' has no port or service",
+            ]
+        );
+        // A disabled rule needs no ports, since it only switches one off.
+        let ids: Vec<&str> = rules.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "off"]);
+        assert_eq!(rules[0].ports, vec![22]);
+        assert_eq!(rules[0].severity, Severity::Medium);
+    }
+
+    #[test]
+    fn custom_rules_override_builtin_ones() {
+        let mut rules = builtin_rules();
+        let (custom, warnings) = parse_rules(
+            "[rule telnet-exposed]\n\
+             port = 23\n\
+             severity = low\n\
+             title = Telnet on the lab network\n\
+             [rule ftp-exposed]\n\
+             enabled = false\n\
+             [rule proxy-open]\n\
+             port = 3128\n\
+             severity = info\n",
+        );
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let builtin_count = rules.len();
+        merge_rules(&mut rules, custom);
+        assert_eq!(rules.len(), builtin_count + 1);
+
+        let mut findings = evaluate_rules(&rules, &[21, 23, 3128, 4444]);
+        sort_findings(&mut findings);
+        let summary: Vec<(&str, Severity)> = findings
+            .iter()
+            .map(|f| (f.rule_id.as_str(), f.severity))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("metasploit-listener", Severity::Critical),
+                ("telnet-exposed", Severity::Low),
+                ("proxy-open", Severity::Info),
+            ]
+        );
+        assert_eq!(findings[1].title, "Telnet on the lab network");
+    }
+
+    #[test]
+    fn orders_severities() {
+        assert!(Severity::Info < Severity::Low);
+        assert!(Severity::Low < Severity::Medium);
+        assert!(Severity::Medium < Severity::High);
+        assert!(Severity::High < Severity::Critical);
+        assert_eq!(Severity::parse(" HIGH "), Some(Severity::High));
+        assert_eq!(Severity::parse("severe"), None);
+    }
+}
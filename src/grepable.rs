@@ -0,0 +1,85 @@
+use crate::engine::{PortResult, PortStatus};
+use crate::resolve::{Resolution, Source};
+use crate::services::get_service_name;
+
+/// A state seen on more ports than this is summed up as `Ignored State`
+/// instead of being listed port by port.
+const IGNORE_THRESHOLD: usize = 25;
+
+fn state(status: PortStatus) -> &'static str {
+    match status {
+        PortStatus::Open => "open",
+        PortStatus::Closed => "closed",
+        PortStatus::TimeoutFiltered => "filtered",
+        PortStatus::HostUnreachable | PortStatus::NetworkUnreachable => "unreachable",
+        PortStatus::PermissionDenied => "denied",
+        PortStatus::ResourceExhausted | PortStatus::InvalidAddress | PortStatus::ProxyError => {
+            "error"
+        }
+    }
+}
+
+/// Service names go in lower case and without characters that the format
+/// uses as separators.
+fn service(port: u16) -> String {
+    match get_service_name(port) {
+        "Unknown" => String::new(),
+        name => name
+            .to_lowercase()
+            .chars()
+            .map(|c| if matches!(c, '/' | ',' | ' ') { '-' } else { c })
+            .collect(),
+    }
+}
+
+/// `Host: <ip> (<name>)`, where the name is the hostname scanned or, for an
+/// address, its reverse DNS name if one was looked up.
+fn host_field(target: &Resolution) -> String {
+    let name = match (&target.source, &target.ptr) {
+        (Source::Literal, Some(ptr)) => ptr.as_str(),
+        (Source::Literal, None) => "",
+        _ => target.target.as_str(),
+    };
+    format!("Host: {} ({})", target.ip(), name)
+}
+
+/// Renders one host in the style of nmap `-oG`: every probed port as
+/// `port/state/tcp//service///`, with the most common non-open state folded
+/// into `Ignored State` once it covers many ports.
+pub fn host_line(target: &Resolution, results: &[PortResult]) -> String {
+    let mut ignored: Option<(PortStatus, usize)> = None;
+    for status in [
+        PortStatus::Closed,
+        PortStatus::TimeoutFiltered,
+        PortStatus::HostUnreachable,
+        PortStatus::NetworkUnreachable,
+        PortStatus::PermissionDenied,
+        PortStatus::ResourceExhausted,
+        PortStatus::InvalidAddress,
+        PortStatus::ProxyError,
+    ] {
+        let count = results.iter().filter(|r| r.status == status).count();
+        if count > IGNORE_THRESHOLD && ignored.is_none_or(|(_, most)| count > most) {
+            ignored = Some((status, count));
+        }
+    }
+
+    let ports: Vec<String> = results
+        .iter()
+        .filter(|r| ignored.is_none_or(|(status, _)| r.status != status))
+        .map(|r| format!("{}/{}/tcp//{}///", r.port, state(r.status), service(r.port)))
+        .collect();
+
+    let mut line = format!("{}\tPorts: {}", host_field(target), ports.join(", "));
+    if let Some((status, count)) = ignored {
+        line.push_str(&format!("\tIgnored State: {} ({})", state(status), count));
+    }
+    line
+}
+
+/// The status line written before the port line of each host; a host whose
+/// scan was abandoned as unreachable is `Down`.
+pub fn status_line(target: &Resolution, up: bool) -> String {
+    let status = if up { "Up" } else { "Down" };
+    format!("{}\tStatus: {}", host_field(target), status)
+}
@@ -0,0 +1,228 @@
+use crate::findings::{Finding, Severity};
+use crate::report::ScanReport;
+use crate::services::get_service_name;
+
+const SEVERITIES: [Severity; 5] = [
+    Severity::Critical,
+    Severity::High,
+    Severity::Medium,
+    Severity::Low,
+    Severity::Info,
+];
+
+const STYLE: &str = "\
+body{font-family:-apple-system,Segoe UI,Helvetica,Arial,sans-serif;margin:0;background:#f4f5f7;color:#1f2328}
+main{max-width:1100px;margin:0 auto;padding:24px}
+h1{margin:0 0 4px}h2{margin:0 0 12px}h3{margin:20px 0 8px}
+.muted{color:#6b7280}
+.card{background:#fff;border:1px solid #d8dee4;border-radius:8px;padding:20px;margin:16px 0}
+.stats{display:flex;flex-wrap:wrap;gap:12px}
+.stat{border:1px solid #d8dee4;border-radius:6px;padding:8px 14px;min-width:110px}
+.stat b{display:block;font-size:1.4em}
+table{border-collapse:collapse;width:100%}
+th,td{text-align:left;padding:6px 10px;border-bottom:1px solid #eaeef2;vertical-align:top}
+th{background:#f6f8fa}
+.badge{display:inline-block;border-radius:4px;padding:1px 8px;font-size:.8em;font-weight:600;color:#fff}
+.sev-critical{background:#8b0000}.sev-high{background:#d1242f}.sev-medium{background:#bf8700}
+.sev-low{background:#0969da}.sev-info{background:#6b7280}
+.finding{border-left:4px solid #d8dee4;padding:4px 12px;margin:8px 0}
+details{border:1px solid #eaeef2;border-radius:6px;padding:6px 10px;margin:6px 0}
+summary{cursor:pointer;font-weight:600}
+code{background:#f6f8fa;padding:1px 4px;border-radius:4px}
+";
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn badge(severity: Severity) -> String {
+    format!(
+        "<span class=\"badge sev-{}\">{}</span>",
+        severity.label().to_lowercase(),
+        severity.label()
+    )
+}
+
+fn stat(label: &str, value: &str) -> String {
+    format!(
+        "<div class=\"stat\"><b>{}</b><span class=\"muted\">{}</span></div>",
+        escape(value),
+        escape(label)
+    )
+}
+
+fn render_finding(finding: &Finding) -> String {
+    let mut html = format!(
+        "<div class=\"finding\">{} <b>Port {}: {}</b>",
+        badge(finding.severity),
+        finding.port,
+        escape(&finding.title)
+    );
+    if !finding.rule_id.is_empty() {
+        html.push_str(&format!(
+            " <span class=\"muted\">(<code>{}</code>)</span>",
+            escape(&finding.rule_id)
+        ));
+    }
+    if !finding.description.is_empty() {
+        html.push_str(&format!("<div>{}</div>", escape(&finding.description)));
+    }
+    if !finding.remediation.is_empty() {
+        html.push_str(&format!(
+            "<div><i>Remediation:</i> {}</div>",
+            escape(&finding.remediation)
+        ));
+    }
+    html.push_str("</div>\n");
+    html
+}
+
+fn render_scan(report: &ScanReport) -> String {
+    let mut html = String::from("<section class=\"card\">\n");
+    let host_name = if report.host_name.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " <span class=\"muted\">({})</span>",
+            escape(&report.host_name)
+        )
+    };
+    html.push_str(&format!(
+        "<h2>{}{} <span class=\"muted\">— {}</span></h2>\n",
+        escape(&report.target),
+        host_name,
+        escape(&report.scan_type)
+    ));
+
+    html.push_str("<div class=\"stats\">");
+    html.push_str(&stat("open ports", &report.open_ports.len().to_string()));
+    html.push_str(&stat("findings", &report.findings.len().to_string()));
+    html.push_str(&stat("ports scanned", &report.total_scanned.to_string()));
+    html.push_str(&stat("duration", &format!("{:.2}s", report.elapsed_secs)));
+    html.push_str(&stat(
+        "ports/second",
+        &format!("{:.1}", report.ports_per_second()),
+    ));
+    html.push_str("</div>\n");
+    html.push_str(&format!(
+        "<p class=\"muted\">Started {} · Finished {}</p>\n",
+        escape(&report.start_time),
+        escape(&report.end_time)
+    ));
+
+    html.push_str("<h3>Open ports</h3>\n");
+    if report.open_ports.is_empty() {
+        html.push_str("<p class=\"muted\">No open ports found.</p>\n");
+    } else {
+        html.push_str("<table><tr><th>Port</th><th>Service</th><th>Details</th></tr>\n");
+        for port in &report.open_ports {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                port,
+                escape(get_service_name(*port)),
+                escape(&report.details_for(*port).join(" · "))
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h3>Findings</h3>\n");
+    if report.findings.is_empty() {
+        html.push_str("<p class=\"muted\">No findings.</p>\n");
+    }
+    for severity in SEVERITIES {
+        for finding in report.findings.iter().filter(|f| f.severity == severity) {
+            html.push_str(&render_finding(finding));
+        }
+    }
+
+    if !report.open_ports.is_empty() {
+        html.push_str("<h3>Port details</h3>\n");
+        for port in &report.open_ports {
+            let details = report.details_for(*port);
+            let findings = report.findings_for(*port);
+            html.push_str(&format!(
+                "<details><summary>Port {} — {} ({} finding(s))</summary>\n",
+                port,
+                escape(get_service_name(*port)),
+                findings.len()
+            ));
+            if details.is_empty() && findings.is_empty() {
+                html.push_str("<p class=\"muted\">Open; no further details recorded.</p>\n");
+            }
+            for detail in details {
+                html.push_str(&format!("<p>{}</p>\n", escape(detail)));
+            }
+            for finding in findings {
+                html.push_str(&render_finding(finding));
+            }
+            html.push_str("</details>\n");
+        }
+    }
+
+    html.push_str("</section>\n");
+    html
+}
+
+/// Renders one or more scans as a single HTML file with inline CSS and no
+/// external assets.
+pub fn render_html(reports: &[ScanReport], generated_at: &str) -> String {
+    let mut html =
+        String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    html.push_str("<title>Vonogs Scan Report</title>\n");
+    html.push_str(&format!(
+        "<style>\n{}</style>\n</head>\n<body>\n<main>\n",
+        STYLE
+    ));
+
+    html.push_str("<h1>Vonogs Scan Report</h1>\n");
+    html.push_str(&format!(
+        "<p class=\"muted\">Generated {} · {} scan(s)</p>\n",
+        escape(generated_at),
+        reports.len()
+    ));
+
+    html.push_str("<section class=\"card\">\n<h2>Summary</h2>\n");
+    html.push_str("<table><tr><th>Target</th><th>Scan type</th><th>Open ports</th>");
+    for severity in SEVERITIES {
+        html.push_str(&format!("<th>{}</th>", badge(severity)));
+    }
+    html.push_str("<th>Duration</th></tr>\n");
+    for report in reports {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td>",
+            escape(&report.target),
+            escape(&report.scan_type),
+            report.open_ports.len()
+        ));
+        for severity in SEVERITIES {
+            let count = report
+                .findings
+                .iter()
+                .filter(|f| f.severity == severity)
+                .count();
+            html.push_str(&format!("<td>{}</td>", count));
+        }
+        html.push_str(&format!("<td>{:.2}s</td></tr>\n", report.elapsed_secs));
+    }
+    html.push_str("</table>\n</section>\n");
+
+    for report in reports {
+        html.push_str(&render_scan(report));
+    }
+
+    html.push_str("</main>\n</body>\n</html>\n");
+    html
+}
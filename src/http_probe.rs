@@ -0,0 +1,159 @@
+use crate::proxy;
+use std::io::{Read, Write};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Default)]
+pub struct HttpInfo {
+    pub status_code: u16,
+    pub reason: String,
+    pub server: Option<String>,
+    pub powered_by: Option<String>,
+    pub title: Option<String>,
+    pub location: Option<String>,
+}
+
+impl HttpInfo {
+    pub fn describe(&self) -> String {
+        let mut parts = vec![format!("{} {}", self.status_code, self.reason)
+            .trim()
+            .to_string()];
+        if let Some(server) = &self.server {
+            parts.push(format!("Server: {}", server));
+        }
+        if let Some(powered_by) = &self.powered_by {
+            parts.push(format!("X-Powered-By: {}", powered_by));
+        }
+        if let Some(title) = &self.title {
+            parts.push(format!("Title: \"{}\"", title));
+        }
+        if let Some(location) = &self.location {
+            parts.push(format!("Location: {}", location));
+        }
+        parts.join(" | ")
+    }
+}
+
+pub fn is_http_candidate(service_name: &str) -> bool {
+    service_name != "HTTPS"
+}
+
+pub fn inspect_http(addr: SocketAddr, host: &str, timeout: Duration) -> Option<HttpInfo> {
+    http_get(addr, host, "/", timeout).map(|(info, _)| info)
+}
+
+/// Sends a plain `GET` for `path` and returns the parsed head with the raw body.
+pub fn http_get(
+    addr: SocketAddr,
+    host: &str,
+    path: &str,
+    timeout: Duration,
+) -> Option<(HttpInfo, String)> {
+    let mut stream = proxy::connect(addr, host, timeout).ok()?;
+    let io_timeout = timeout.max(Duration::from_secs(2));
+    let _ = stream.set_read_timeout(Some(io_timeout));
+    let _ = stream.set_write_timeout(Some(io_timeout));
+
+    let request = format!(
+        "GET {} HTTP/1.1\r\n\
+         Host: {}\r\n\
+         User-Agent: vonogs-sentinel\r\n\
+         Accept: */*\r\n\
+         Connection: close\r\n\r\n",
+        path,
+        host_header(host, addr)
+    );
+    stream.write_all(request.as_bytes()).ok()?;
+
+    let mut response = Vec::new();
+    let mut buf = [0u8; 4096];
+    while response.len() < MAX_RESPONSE_BYTES {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => response.extend_from_slice(&buf[..n]),
+            Err(_) => break,
+        }
+    }
+
+    parse_response(&response)
+}
+
+fn host_header(host: &str, addr: SocketAddr) -> String {
+    let host = host.trim();
+    let name = if host.is_empty() {
+        addr.ip().to_string()
+    } else if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    };
+
+    match addr.port() {
+        80 => name,
+        port => format!("{}:{}", name, port),
+    }
+}
+
+fn parse_response(response: &[u8]) -> Option<(HttpInfo, String)> {
+    let text = String::from_utf8_lossy(response);
+    let (head, body) = match text.find("\r\n\r\n") {
+        Some(idx) => (&text[..idx], &text[idx + 4..]),
+        None => (text.as_ref(), ""),
+    };
+
+    let mut lines = head.lines();
+    let status_line = lines.next()?;
+    if !status_line.starts_with("HTTP/") {
+        return None;
+    }
+
+    let mut status_parts = status_line.splitn(3, ' ');
+    let _version = status_parts.next()?;
+    let status_code = status_parts.next()?.trim().parse::<u16>().ok()?;
+    let reason = status_parts.next().unwrap_or("").trim().to_string();
+
+    let mut info = HttpInfo {
+        status_code,
+        reason,
+        ..Default::default()
+    };
+
+    for line in lines {
+        let (name, value) = match line.split_once(':') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        match name.trim().to_ascii_lowercase().as_str() {
+            "server" => info.server = Some(value.to_string()),
+            "x-powered-by" => info.powered_by = Some(value.to_string()),
+            "location" => info.location = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    info.title = extract_title(body);
+    Some((info, body.to_string()))
+}
+
+fn extract_title(body: &str) -> Option<String> {
+    let lower = body.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let content_start = open + lower[open..].find('>')? + 1;
+    let content_end = content_start + lower[content_start..].find("</title")?;
+
+    let title = body[content_start..content_end]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if title.is_empty() {
+        return None;
+    }
+
+    Some(title.chars().take(120).collect())
+}
@@ -0,0 +1,93 @@
+use crate::html_report::escape;
+use crate::policy::PolicyCheck;
+use crate::services::get_service_name;
+
+/// The policy checks for one host, rendered as one `<testsuite>`.
+pub struct HostSuite {
+    pub target: String,
+    pub checks: Vec<PolicyCheck>,
+    /// Set when the host could not be scanned at all, e.g. it did not resolve.
+    pub error: Option<String>,
+    pub elapsed_secs: f32,
+}
+
+impl HostSuite {
+    fn failures(&self) -> usize {
+        self.checks.iter().filter(|c| !c.passed).count()
+    }
+
+    fn tests(&self) -> usize {
+        self.checks.len() + usize::from(self.error.is_some())
+    }
+}
+
+fn render_check(check: &PolicyCheck) -> String {
+    let mut xml = format!(
+        "    <testcase classname=\"{}\" name=\"{}\" time=\"0\"",
+        escape(&format!("policy.{}", check.target)),
+        escape(&format!(
+            "port {} {}",
+            check.port,
+            get_service_name(check.port)
+        ))
+    );
+    match check.finding() {
+        None => xml.push_str("/>\n"),
+        Some(finding) => xml.push_str(&format!(
+            ">\n      <failure message=\"{}\" type=\"{}\">{}</failure>\n    </testcase>\n",
+            escape(&check.message()),
+            escape(&finding.rule_id),
+            escape(&finding.remediation)
+        )),
+    }
+    xml
+}
+
+fn render_suite(suite: &HostSuite) -> String {
+    let mut xml = format!(
+        "  <testsuite name=\"{}\" hostname=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"0\" time=\"{:.3}\">\n",
+        escape(&format!("policy.{}", suite.target)),
+        escape(&suite.target),
+        suite.tests(),
+        suite.failures(),
+        usize::from(suite.error.is_some()),
+        suite.elapsed_secs
+    );
+    if let Some(error) = &suite.error {
+        xml.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"scan {}\" time=\"0\">\n      <error message=\"{}\" type=\"policy-target-unusable\"/>\n    </testcase>\n",
+            escape(&format!("policy.{}", suite.target)),
+            escape(&suite.target),
+            escape(error)
+        ));
+    }
+    for check in &suite.checks {
+        xml.push_str(&render_check(check));
+    }
+    xml.push_str("  </testsuite>\n");
+    xml
+}
+
+/// Renders a policy run as JUnit XML: one test suite per host and one test
+/// case per scanned port, failing where the port breaks its expectation.
+pub fn render_junit(name: &str, suites: &[HostSuite]) -> String {
+    let tests: usize = suites.iter().map(HostSuite::tests).sum();
+    let failures: usize = suites.iter().map(HostSuite::failures).sum();
+    let errors = suites.iter().filter(|s| s.error.is_some()).count();
+    let time: f32 = suites.iter().map(|s| s.elapsed_secs).sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+        escape(name),
+        tests,
+        failures,
+        errors,
+        time
+    ));
+    for suite in suites {
+        xml.push_str(&render_suite(suite));
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
@@ -1,27 +1,143 @@
+mod cli;
+mod color;
+mod dns;
+mod engine;
+mod exposure;
+mod findings;
+mod grepable;
+mod html_report;
+mod http_probe;
+mod json;
+mod junit;
+mod markdown_report;
+mod metrics;
+mod policy;
+mod profiles;
+mod progress;
+mod proxy;
+mod report;
+mod resolve;
+mod sarif;
+mod server;
 mod services;
+mod sha256;
+mod ssh;
+mod starttls;
+mod tls;
+mod trace;
+mod tui;
 mod validation;
-
-use services::get_service_name;
+mod watch;
+mod webhook;
+mod x509;
+
+use cli::{parse_args, take_global_options, Command, ScanOutput, USAGE};
+use color::{bold, cyan, green, magenta, red, reset, yellow, ColorChoice};
+use engine::{
+    abort_note, connect_timeout, host_abort, probe_port, scan_ports, HostWatch, PortResult,
+    PortStatus,
+};
+use exposure::{check_exposure, is_exposure_port};
+use findings::{evaluate_rules, load_rules, sort_findings, Finding, Rule, Severity};
+use http_probe::{inspect_http, is_http_candidate};
+use junit::{render_junit, HostSuite};
+use metrics::{Metrics, SharedMetrics};
+use policy::{
+    evaluate as evaluate_policy, exit_code as policy_exit_code, parse_policy, Expectation,
+};
+use profiles::ScanProfile;
+use progress::Progress;
+use report::{parse_log, ReportFormat, ScanReport};
+use resolve::{resolve, Resolution, Source};
+use sarif::render_sarif;
+use services::{get_service_name, is_tls_port};
+use ssh::{inspect_ssh, is_ssh_service};
+use starttls::{inspect_starttls, is_starttls_port};
+use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io;
 use std::io::{stdin, stdout, Read, Write};
-use std::net::TcpStream;
-use std::net::ToSocketAddrs;
+use std::net::IpAddr;
 use std::path::Path;
 use std::process;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tls::{expiry_warning_days as tls_expiry_warning_days, inspect_tls, TlsInfo};
+use tui::{HomeAction, HostScan, Terminal};
 use validation::{is_valid_ip, is_valid_port};
-
-const RESET: &str = "\x1b[0m";
-const RED: &str = "\x1b[31m";
-const GREEN: &str = "\x1b[32m";
-const YELLOW: &str = "\x1b[33m";
-const CYAN: &str = "\x1b[36m";
-const BOLD: &str = "\x1b[1m";
+use watch::{
+    append_line, diff_states, fingerprint, open_ports, parse_watch_config, ChangeKind, HostState,
+    RotatingLog,
+};
+use webhook::{change_payload, config_from_env as webhook_config, scan_payload, WebhookConfig};
 
 fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let options = take_global_options(&mut args);
+    match &options {
+        Ok(options) => {
+            color::init(options.color);
+            trace::set_level(options.verbosity);
+        }
+        Err(_) => color::init(ColorChoice::Auto),
+    }
+    let proxy_url = options.as_ref().ok().and_then(|o| o.proxy.clone());
+    let command = options.and_then(|_| parse_args(&args));
+    if command.as_ref().is_ok_and(Command::uses_network) {
+        if let Err(message) = configure_network(proxy_url.as_deref()) {
+            eprintln!("{}Error: {}{}", red(), message, reset());
+            process::exit(2);
+        }
+    }
+    match command {
+        Ok(Command::Interactive) => {}
+        Ok(Command::Scan {
+            targets,
+            scan_type,
+            ports,
+            output,
+        }) => process::exit(scan_command(&targets, &scan_type, &ports, output)),
+        Ok(Command::Policy { file, sarif, junit }) => {
+            process::exit(policy_check(&file, sarif.as_deref(), junit.as_deref()))
+        }
+        Ok(Command::Watch { file }) => process::exit(watch_mode(&file)),
+        Ok(Command::Serve { listen }) => process::exit(serve_api(&listen)),
+        Ok(Command::Report {
+            logs,
+            output,
+            format,
+        }) => process::exit(build_report(&logs, output, format)),
+        Ok(Command::WebhookTest) => process::exit(webhook_test()),
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(message) => {
+            eprintln!("{}Error: {}{}\n", red(), message, reset());
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+
     create_logs_directory();
+    if tui::is_available() {
+        dashboard_menu();
+    }
+    classic_menu();
+}
+
+/// Checks `VONOGS_DNS_SERVER` and sets up the `--proxy`, whose host is
+/// resolved through that server.
+fn configure_network(proxy_url: Option<&str>) -> Result<(), String> {
+    dns::server_from_env()?;
+    if let Some(url) = proxy_url {
+        proxy::set(proxy::Proxy::parse(url)?);
+    }
+    Ok(())
+}
+
+fn classic_menu() -> ! {
     print_menu_items();
 
     loop {
@@ -37,6 +153,148 @@ fn main() {
     }
 }
 
+/// Full-screen start screen and dashboard; returns when the user asks for
+/// the classic menu or the terminal cannot be switched to raw mode.
+fn dashboard_menu() {
+    let terminal = match Terminal::enter() {
+        Ok(terminal) => terminal,
+        Err(_) => return,
+    };
+    let title = format!("{} v{}", scanner_info().name, scanner_info().version);
+    let mut messages = Vec::new();
+
+    loop {
+        match tui::home(&terminal, &title, &messages) {
+            HomeAction::Scan {
+                scan_type,
+                targets,
+                ports,
+            } => {
+                let hosts = tui::run_dashboard(&terminal, &scan_type, &targets, &ports);
+                messages = save_dashboard_logs(&scan_type, &hosts);
+            }
+            HomeAction::ClassicMenu => return,
+            HomeAction::Quit => break,
+        }
+    }
+    drop(terminal);
+    end_program();
+}
+
+/// Writes one log per dashboard host in the usual format, so `vonogs report`
+/// and webhooks treat them like menu scans. Returns lines for the start screen.
+fn save_dashboard_logs(scan_type: &str, hosts: &[HostScan]) -> Vec<String> {
+    let (rules, warnings) = load_rules();
+    let mut messages: Vec<String> = warnings
+        .iter()
+        .map(|w| format!("{}Warning: {}{}", yellow(), w, reset()))
+        .collect();
+
+    for host in hosts {
+        let (log_path, report) = save_host_log("dashboard", scan_type, host, &rules);
+
+        let outcome = match (&host.error, host.is_incomplete()) {
+            (Some(error), _) => format!("{}{}{}", red(), error, reset()),
+            (None, true) => format!("{}cancelled{}", yellow(), reset()),
+            (None, false) => format!(
+                "{}{} open{}, {} finding(s)",
+                green(),
+                report.open_ports.len(),
+                reset(),
+                report.findings.len()
+            ),
+        };
+        messages.push(format!(
+            "{}{}{}: {} · log {}{}{}",
+            cyan(),
+            host.target,
+            reset(),
+            outcome,
+            bold(),
+            log_path,
+            reset()
+        ));
+    }
+    messages
+}
+
+/// Writes one host's results to `scan_logs/scan_<time>_<prefix>_<host>.log`
+/// and sends the completion webhook; returns the log path and the report.
+fn save_host_log(
+    prefix: &str,
+    scan_type: &str,
+    host: &HostScan,
+    rules: &[Rule],
+) -> (String, ScanReport) {
+    let log_name: String = host
+        .target
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let (mut log_file, log_path) = create_log_file(&format!("{}_{}", prefix, log_name));
+    let start_time = write_log_header(
+        &mut log_file,
+        scan_type,
+        &host.target,
+        host.resolution.as_deref(),
+    );
+    if let Some(error) = &host.error {
+        write_log_entry(&mut log_file, &format!("Error: {} {}", host.target, error));
+    }
+    for result in &host.results {
+        let entry = match result.status {
+            PortStatus::Open => format!(
+                "Port {}: {} - OPEN",
+                result.port,
+                get_service_name(result.port)
+            ),
+            _ => format!("Port {}: {}", result.port, result.describe()),
+        };
+        write_log_entry(&mut log_file, &entry);
+    }
+    StatusCounts::tally(&host.results).log(&mut log_file);
+    if host.is_incomplete() {
+        write_log_entry(
+            &mut log_file,
+            &format!(
+                "Scan cancelled after {} of {} ports",
+                host.results.len(),
+                host.ports.len()
+            ),
+        );
+    }
+
+    let open_ports = host.open_ports();
+    let mut findings = evaluate_rules(rules, &open_ports);
+    sort_findings(&mut findings);
+    let report = ScanReport {
+        scan_type: scan_type.to_string(),
+        target: host.target.clone(),
+        host_name: host
+            .resolution
+            .as_ref()
+            .and_then(|r| r.ptr.clone())
+            .unwrap_or_default(),
+        start_time,
+        end_time: get_timestamp(),
+        total_scanned: host.results.len() as u32,
+        elapsed_secs: host.elapsed_secs,
+        open_ports,
+        details: Vec::new(),
+        findings,
+    };
+    write_log_summary(&mut log_file, &report);
+    if host.error.is_none() {
+        notify_scan_complete(
+            &report.scan_type,
+            &report.target,
+            &report.open_ports,
+            &report.findings,
+        );
+    }
+    (log_path, report)
+}
+
 fn create_logs_directory() {
     if !Path::new("scan_logs").exists() {
         let _ = fs::create_dir("scan_logs");
@@ -139,38 +397,52 @@ fn create_log_file(scan_type: &str) -> (File, String) {
     match File::create(&filename) {
         Ok(f) => (f, filename),
         Err(_) => {
-            println!("{}Warning: Could not create log file{}", YELLOW, RESET);
+            eprintln!("{}Warning: Could not create log file{}", yellow(), reset());
             let f = File::create(null_log_path()).unwrap();
             (f, String::from("(no log file created)"))
         }
     }
 }
 
-fn write_log_header(log_file: &mut File, scan_type: &str, target_ip: &str) {
+/// Writes the log header and returns the start time it recorded.
+/// A hostname target also gets the addresses it resolved to, which every
+/// port of the scan was probed on, and any target its reverse DNS name.
+/// Scans through a proxy name it.
+fn write_log_header(
+    log_file: &mut File,
+    scan_type: &str,
+    target_ip: &str,
+    resolution: Option<&Resolution>,
+) -> String {
     let timestamp = get_timestamp();
+    let mut resolved: String = resolution
+        .map(Resolution::log_lines)
+        .unwrap_or_default()
+        .iter()
+        .map(|line| format!("{}\n", line))
+        .collect();
+    if let Some(proxy) = proxy::current() {
+        resolved.push_str(&format!("Proxy: {}\n", proxy.label()));
+    }
     let header = format!(
         "=================================\n\
          Vonogs Scanner Log\n\
          =================================\n\
          Scan Type: {}\n\
          Target: {}\n\
-         Start Time: {}\n\
+         {}Start Time: {}\n\
          =================================\n\n",
-        scan_type, target_ip, timestamp
+        scan_type, target_ip, resolved, timestamp
     );
     let _ = log_file.write_all(header.as_bytes());
+    timestamp
 }
 
 fn write_log_entry(log_file: &mut File, message: &str) {
     let _ = log_file.write_all(format!("{}\n", message).as_bytes());
 }
 
-fn write_log_summary(
-    log_file: &mut File,
-    open_ports: &Vec<u16>,
-    total_scanned: u32,
-    elapsed_secs: f32,
-) {
+fn write_log_summary(log_file: &mut File, report: &ScanReport) {
     let summary = format!(
         "\n=================================\n\
          Scan Summary\n\
@@ -178,111 +450,644 @@ fn write_log_summary(
          Total Ports Scanned: {}\n\
          Open Ports Found: {}\n\
          Scan Duration: {:.2} seconds\n",
-        total_scanned,
-        open_ports.len(),
-        elapsed_secs
+        report.total_scanned,
+        report.open_ports.len(),
+        report.elapsed_secs
     );
     let _ = log_file.write_all(summary.as_bytes());
 
-    if !open_ports.is_empty() {
+    if !report.open_ports.is_empty() {
         let _ = log_file.write_all(b"\nOpen Ports:\n");
-        for port in open_ports {
+        for port in &report.open_ports {
             let service = get_service_name(*port);
             let entry = format!("  Port {}: {} (OPEN)\n", port, service);
             let _ = log_file.write_all(entry.as_bytes());
         }
     }
 
-    let end_time = format!("\nEnd Time: {}\n", get_timestamp());
+    if !report.details.is_empty() {
+        let _ = log_file.write_all(b"\nService Details:\n");
+        for (port, details) in &report.details {
+            let entry = format!("  Port {}: {}\n", port, details);
+            let _ = log_file.write_all(entry.as_bytes());
+        }
+    }
+
+    if !report.findings.is_empty() {
+        let _ = log_file.write_all(b"\nFindings:\n");
+        for finding in &report.findings {
+            let entry = format!(
+                "  {}\n    Rule: {}\n    {}\n    Remediation: {}\n",
+                finding.summary_line(),
+                finding.rule_id,
+                finding.description,
+                finding.remediation
+            );
+            let _ = log_file.write_all(entry.as_bytes());
+        }
+    }
+
+    let end_time = format!("\nEnd Time: {}\n", report.end_time);
     let _ = log_file.write_all(end_time.as_bytes());
 }
 
-fn resolve_addr(target: &str, port: u16) -> Option<std::net::SocketAddr> {
-    let mut last_v6 = None;
-    if let Ok(iter) = (target, port).to_socket_addrs() {
-        for addr in iter {
-            if addr.is_ipv4() {
-                return Some(addr);
-            } else {
-                last_v6 = Some(addr);
-            }
+fn ask_report_formats() -> Vec<ReportFormat> {
+    println!("Save a report? (html, md, sarif - comma-separated - or n)");
+    let input = read_input("").unwrap_or_default();
+    let mut formats = Vec::new();
+    for name in input.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        match ReportFormat::from_name(name) {
+            Some(format) if !formats.contains(&format) => formats.push(format),
+            Some(_) => {}
+            None if name.eq_ignore_ascii_case("n") => return Vec::new(),
+            None => println!("{}Unknown report format '{}'{}", yellow(), name, reset()),
+        }
+    }
+    formats
+}
+
+/// Writes the chosen reports next to the scan log.
+fn offer_reports(report: &ScanReport, log_path: &str) {
+    if !log_path.ends_with(".log") {
+        return;
+    }
+    for format in ask_report_formats() {
+        let report_path = format!(
+            "{}.{}",
+            log_path.trim_end_matches(".log"),
+            format.extension()
+        );
+        let contents = format.render(std::slice::from_ref(report), &get_timestamp());
+        match fs::write(&report_path, contents) {
+            Ok(()) => println!(
+                "{}Report saved to {}{}{}",
+                cyan(),
+                bold(),
+                report_path,
+                reset()
+            ),
+            Err(e) => println!(
+                "{}Warning: could not write {}: {}{}",
+                yellow(),
+                report_path,
+                e,
+                reset()
+            ),
         }
     }
-    last_v6
 }
 
-fn connect_timeout() -> Duration {
-    std::env::var("VONOGS_TIMEOUT_MS")
-        .ok()
-        .and_then(|v| v.trim().parse::<u64>().ok())
-        .map(Duration::from_millis)
-        .unwrap_or(Duration::from_millis(700))
+/// Ports that were not open, counted by status; closed ports are also
+/// split by why the connection failed.
+#[derive(Debug, Default)]
+struct StatusCounts {
+    statuses: Vec<(PortStatus, usize)>,
+    closed: Vec<(String, usize)>,
 }
 
-#[derive(Debug, Clone, Copy)]
-enum PortStatus {
-    Open,
-    Closed,
-    TimeoutFiltered,
-    InvalidAddress,
+fn bump<T: PartialEq>(counts: &mut Vec<(T, usize)>, key: T) {
+    match counts.iter_mut().find(|(k, _)| *k == key) {
+        Some((_, count)) => *count += 1,
+        None => counts.push((key, 1)),
+    }
 }
 
-fn scan_port(target: &str, port: u16) -> PortStatus {
-    let socket_addr = match resolve_addr(target, port) {
-        Some(addr) => addr,
-        None => return PortStatus::InvalidAddress,
-    };
+impl StatusCounts {
+    fn tally(results: &[PortResult]) -> StatusCounts {
+        let mut counts = StatusCounts::default();
+        for result in results {
+            counts.add(result);
+        }
+        counts
+    }
 
-    match TcpStream::connect_timeout(&socket_addr, connect_timeout()) {
-        Ok(_) => PortStatus::Open,
-        Err(e) => {
-            if e.kind() == io::ErrorKind::TimedOut {
-                PortStatus::TimeoutFiltered
+    fn add(&mut self, result: &PortResult) {
+        if result.status == PortStatus::Open {
+            return;
+        }
+        bump(&mut self.statuses, result.status);
+        if result.status == PortStatus::Closed {
+            bump(&mut self.closed, result.reason.label());
+        }
+    }
+
+    /// One entry per status, most common first, e.g. `closed 1016
+    /// (connection refused 1014, connection reset 2)` or `host unreachable 3`.
+    fn parts(&self) -> Vec<(PortStatus, String)> {
+        let mut statuses = self.statuses.clone();
+        statuses.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        statuses
+            .iter()
+            .map(|(status, count)| {
+                let mut part = format!("{} {}", status.label().to_lowercase(), count);
+                if *status == PortStatus::Closed {
+                    let mut reasons = self.closed.clone();
+                    reasons.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+                    let reasons: Vec<String> = reasons
+                        .iter()
+                        .map(|(reason, count)| format!("{} {}", reason, count))
+                        .collect();
+                    part.push_str(&format!(" ({})", reasons.join(", ")));
+                }
+                (*status, part)
+            })
+            .collect()
+    }
+
+    fn print(&self, indent: &str) {
+        let parts: Vec<String> = self
+            .parts()
+            .iter()
+            .map(|(status, part)| format!("{}{}{}", status_colour(*status), part, reset()))
+            .collect();
+        if !parts.is_empty() {
+            println!("{}Not open: {}", indent, parts.join(", "));
+        }
+    }
+
+    fn log(&self, log_file: &mut File) {
+        let parts: Vec<String> = self.parts().into_iter().map(|(_, part)| part).collect();
+        if !parts.is_empty() {
+            write_log_entry(log_file, &format!("\nNot Open: {}", parts.join(", ")));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct InspectionOptions {
+    http: bool,
+    tls: bool,
+    starttls: bool,
+    ssh: bool,
+    exposure: bool,
+}
+
+impl InspectionOptions {
+    fn ask(ports: &[u16], http: bool) -> InspectionOptions {
+        let tls = ports.iter().any(|p| is_tls_port(*p)) && ask_yes_no("Inspect TLS on TLS ports?");
+        let starttls = ports.iter().any(|p| is_starttls_port(*p))
+            && ask_yes_no("Probe STARTTLS on mail/directory ports?");
+        let ssh = ports.iter().any(|p| is_ssh_service(get_service_name(*p)))
+            && ask_yes_no("Audit SSH server algorithms?");
+        let exposure = ports.iter().any(|p| is_exposure_port(*p))
+            && ask_yes_no("Check databases and caches for unauthenticated access?");
+        InspectionOptions {
+            http,
+            tls,
+            starttls,
+            ssh,
+            exposure,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct InspectionResults {
+    details: Vec<(u16, String)>,
+    starttls: Vec<(u16, bool)>,
+    findings: Vec<Finding>,
+}
+
+impl InspectionResults {
+    fn service_label(&self, port: u16) -> String {
+        let service = get_service_name(port);
+        match self.starttls.iter().find(|(p, _)| *p == port) {
+            Some((_, true)) => format!("{} [STARTTLS: yes]", service),
+            Some((_, false)) => format!("{} [STARTTLS: no]", service),
+            None => service.to_string(),
+        }
+    }
+}
+
+fn inspect_open_port(
+    resolution: &Resolution,
+    port: u16,
+    options: InspectionOptions,
+    log_file: &mut File,
+    results: &mut InspectionResults,
+) {
+    if options.http && is_http_candidate(get_service_name(port)) {
+        inspect_http_port(resolution, port, log_file, &mut results.details);
+    }
+    if options.tls && is_tls_port(port) {
+        inspect_tls_port(resolution, port, log_file, results);
+    }
+    if options.starttls && is_starttls_port(port) {
+        inspect_starttls_port(resolution, port, log_file, results);
+    }
+    if options.ssh && is_ssh_service(get_service_name(port)) {
+        inspect_ssh_port(resolution, port, log_file, results);
+    }
+    if options.exposure && is_exposure_port(port) {
+        inspect_exposure_port(resolution, port, log_file, results);
+    }
+}
+
+fn inspect_http_port(
+    resolution: &Resolution,
+    port: u16,
+    log_file: &mut File,
+    service_details: &mut Vec<(u16, String)>,
+) {
+    show_status("  Inspecting HTTP... ");
+    let http_info = inspect_http(
+        resolution.socket_addr(port),
+        &resolution.target,
+        connect_timeout(),
+    );
+    clear_status();
+
+    match http_info {
+        Some(info) => {
+            let details = info.describe();
+            println!("    {}↳{} {}", cyan(), reset(), details);
+            write_log_entry(log_file, &format!("    HTTP: {}", details));
+            service_details.push((port, format!("HTTP {}", details)));
+        }
+        None => {
+            println!("    {}↳ no HTTP response{}", yellow(), reset());
+            write_log_entry(log_file, "    HTTP: no response");
+        }
+    }
+}
+
+fn inspect_tls_port(
+    resolution: &Resolution,
+    port: u16,
+    log_file: &mut File,
+    results: &mut InspectionResults,
+) {
+    show_status("  Inspecting TLS... ");
+    let tls_info = inspect_tls(
+        resolution.socket_addr(port),
+        &resolution.target,
+        connect_timeout(),
+    );
+    clear_status();
+
+    match tls_info {
+        Ok(info) => {
+            let details = info.describe();
+            let warnings = info.warnings(tls_expiry_warning_days());
+            println!("    {}↳{} {}", cyan(), reset(), details);
+            write_log_entry(log_file, &format!("    TLS: {}", details));
+            for warning in &warnings {
+                println!("    {}{}⚠ {}{}", red(), bold(), warning, reset());
+                write_log_entry(log_file, &format!("    TLS WARNING: {}", warning));
+            }
+
+            let mut summary = format!("TLS {}", details);
+            if !warnings.is_empty() {
+                summary.push_str(&format!(" [{}]", warnings.join(", ")));
+                results
+                    .findings
+                    .push(certificate_finding(port, &warnings, &details));
+            }
+            record_tls_weaknesses(port, &info, &mut summary, log_file, results);
+            results.details.push((port, summary));
+        }
+        Err(reason) => {
+            println!(
+                "    {}↳ TLS inspection failed: {}{}",
+                yellow(),
+                reason,
+                reset()
+            );
+            write_log_entry(
+                log_file,
+                &format!("    TLS: inspection failed ({})", reason),
+            );
+        }
+    }
+}
+
+fn inspect_starttls_port(
+    resolution: &Resolution,
+    port: u16,
+    log_file: &mut File,
+    results: &mut InspectionResults,
+) {
+    show_status("  Probing STARTTLS... ");
+    let starttls_info = inspect_starttls(
+        resolution.socket_addr(port),
+        &resolution.target,
+        connect_timeout(),
+    );
+    clear_status();
+
+    match starttls_info {
+        Ok(info) => {
+            let details = info.describe();
+            let colour = if info.supported { cyan() } else { red() };
+            println!("    {}↳{} {}", colour, reset(), details);
+            write_log_entry(log_file, &format!("    STARTTLS: {}", details));
+
+            let mut summary = details;
+            if let Some(Ok(tls_info)) = &info.tls {
+                let warnings = tls_info.warnings(tls_expiry_warning_days());
+                for warning in &warnings {
+                    println!("    {}{}⚠ {}{}", red(), bold(), warning, reset());
+                    write_log_entry(log_file, &format!("    TLS WARNING: {}", warning));
+                }
+                if !warnings.is_empty() {
+                    summary.push_str(&format!(" [{}]", warnings.join(", ")));
+                    results.findings.push(certificate_finding(
+                        port,
+                        &warnings,
+                        &tls_info.describe(),
+                    ));
+                }
+                record_tls_weaknesses(port, tls_info, &mut summary, log_file, results);
+            }
+            results.details.push((port, summary));
+            results.starttls.push((port, info.supported));
+        }
+        Err(reason) => {
+            println!(
+                "    {}↳ STARTTLS probe failed: {}{}",
+                yellow(),
+                reason,
+                reset()
+            );
+            write_log_entry(
+                log_file,
+                &format!("    STARTTLS: probe failed ({})", reason),
+            );
+        }
+    }
+}
+
+fn inspect_ssh_port(
+    resolution: &Resolution,
+    port: u16,
+    log_file: &mut File,
+    results: &mut InspectionResults,
+) {
+    show_status("  Auditing SSH... ");
+    let ssh_info = inspect_ssh(
+        resolution.socket_addr(port),
+        &resolution.target,
+        connect_timeout(),
+    );
+    clear_status();
+
+    match ssh_info {
+        Ok(info) => {
+            let details = info.describe();
+            println!("    {}↳{} {}", cyan(), reset(), details);
+            write_log_entry(log_file, &format!("    SSH: {}", info.identification));
+            for (category, algorithms) in info.algorithm_lists() {
+                write_log_entry(
+                    log_file,
+                    &format!("    SSH {}: {}", category, algorithms.join(", ")),
+                );
+            }
+
+            let weaknesses = info.weaknesses();
+            for weakness in &weaknesses {
+                println!("    {}{}⚠ weak {}{}", red(), bold(), weakness, reset());
+                write_log_entry(log_file, &format!("    SSH WEAK: {}", weakness));
+            }
+
+            let mut summary = format!("SSH {}", details);
+            if weaknesses.is_empty() {
+                summary.push_str(" | no weak algorithms offered");
             } else {
-                PortStatus::Closed
+                summary.push_str(&format!(" | weak: {}", weaknesses.join(", ")));
+                results.findings.push(Finding {
+                    rule_id: String::from("ssh-weak-algorithms"),
+                    port,
+                    severity: Severity::Medium,
+                    title: String::from("Weak SSH algorithms offered"),
+                    description: format!(
+                        "{} offers: {}.",
+                        info.identification,
+                        weaknesses.join(", ")
+                    ),
+                    remediation: String::from(
+                        "Remove the listed algorithms from the server's KexAlgorithms, \
+                         HostKeyAlgorithms, Ciphers and MACs settings.",
+                    ),
+                });
+            }
+            results.details.push((port, summary));
+        }
+        Err(reason) => {
+            println!("    {}↳ SSH audit failed: {}{}", yellow(), reason, reset());
+            write_log_entry(log_file, &format!("    SSH: audit failed ({})", reason));
+        }
+    }
+}
+
+fn inspect_exposure_port(
+    resolution: &Resolution,
+    port: u16,
+    log_file: &mut File,
+    results: &mut InspectionResults,
+) {
+    show_status("  Checking authentication... ");
+    let exposure_info = check_exposure(
+        resolution.socket_addr(port),
+        &resolution.target,
+        connect_timeout(),
+    );
+    clear_status();
+
+    match exposure_info {
+        Ok(info) => {
+            let details = info.describe();
+            write_log_entry(log_file, &format!("    AUTH: {}", details));
+            match info.finding(port) {
+                Some(finding) => {
+                    println!("    {}{}⚠ {}{}", red(), bold(), details, reset());
+                    write_log_entry(
+                        log_file,
+                        &format!("    FINDING: {}", finding.summary_line()),
+                    );
+                    results.findings.push(finding);
+                }
+                None => println!("    {}↳{} {}", cyan(), reset(), details),
             }
+            results.details.push((port, details));
         }
+        Err(reason) => {
+            println!(
+                "    {}↳ authentication check failed: {}{}",
+                yellow(),
+                reason,
+                reset()
+            );
+            write_log_entry(log_file, &format!("    AUTH: check failed ({})", reason));
+        }
+    }
+}
+
+fn certificate_finding(port: u16, warnings: &[String], details: &str) -> Finding {
+    let expired = warnings.iter().any(|w| w == "EXPIRED");
+    Finding {
+        rule_id: String::from("tls-certificate"),
+        port,
+        severity: if expired {
+            Severity::High
+        } else {
+            Severity::Medium
+        },
+        title: format!("TLS certificate issue: {}", warnings.join(", ")),
+        description: details.to_string(),
+        remediation: String::from(
+            "Install a certificate issued by a trusted CA and renew it before it expires.",
+        ),
+    }
+}
+
+/// Reports a weak negotiated protocol or cipher suite, shared by the TLS
+/// and STARTTLS inspections.
+fn record_tls_weaknesses(
+    port: u16,
+    info: &TlsInfo,
+    summary: &mut String,
+    log_file: &mut File,
+    results: &mut InspectionResults,
+) {
+    let weaknesses = info.weaknesses();
+    if weaknesses.is_empty() {
+        return;
+    }
+    for weakness in &weaknesses {
+        println!("    {}{}⚠ weak {}{}", red(), bold(), weakness, reset());
+        write_log_entry(log_file, &format!("    TLS WEAK: {}", weakness));
+    }
+    summary.push_str(&format!(" | weak: {}", weaknesses.join(", ")));
+    results.findings.push(Finding {
+        rule_id: String::from("tls-weak-negotiation"),
+        port,
+        severity: Severity::Medium,
+        title: String::from("Weak TLS protocol or cipher negotiated"),
+        description: format!(
+            "The server negotiated {} {}: {}.",
+            info.version,
+            info.cipher_suite,
+            weaknesses.join(", ")
+        ),
+        remediation: String::from(
+            "Require TLS 1.2 or newer and disable RC4, DES, 3DES, NULL, export and \
+             anonymous cipher suites.",
+        ),
+    });
+}
+
+/// Applies the finding rules to the open ports and orders all findings
+/// gathered during the scan by severity.
+fn evaluate_findings(open_ports: &[u16], results: &mut InspectionResults) {
+    let (rules, warnings) = load_rules();
+    for warning in warnings {
+        println!("{}Warning: {}{}", yellow(), warning, reset());
     }
+    results.findings.extend(evaluate_rules(&rules, open_ports));
+    sort_findings(&mut results.findings);
 }
 
-fn resolve_target_note(target: &str) -> Option<String> {
-    if is_valid_ip(target) {
-        return None;
+fn status_colour(status: PortStatus) -> &'static str {
+    match status {
+        PortStatus::Open => green(),
+        PortStatus::TimeoutFiltered => yellow(),
+        PortStatus::HostUnreachable | PortStatus::NetworkUnreachable => magenta(),
+        PortStatus::Closed
+        | PortStatus::PermissionDenied
+        | PortStatus::ResourceExhausted
+        | PortStatus::InvalidAddress
+        | PortStatus::ProxyError => red(),
+    }
+}
+
+fn severity_colour(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::High => red(),
+        Severity::Medium => yellow(),
+        Severity::Low | Severity::Info => cyan(),
+    }
+}
+
+fn print_findings(findings: &[Finding]) {
+    if findings.is_empty() {
+        return;
+    }
+
+    println!("\n{}Findings{}:", yellow(), reset());
+    for finding in findings {
+        println!(
+            "  {}{}[{}]{} Port {}{}{}: {}",
+            severity_colour(finding.severity),
+            bold(),
+            finding.severity.label(),
+            reset(),
+            yellow(),
+            finding.port,
+            reset(),
+            finding.title
+        );
+        println!("      {}", finding.description);
+        println!(
+            "      {}Remediation:{} {}",
+            cyan(),
+            reset(),
+            finding.remediation
+        );
     }
+}
+
+/// Reports a host given up on after repeated host-level failures.
+fn abandon_host(
+    progress: &Progress,
+    log_file: &mut File,
+    status: PortStatus,
+    scanned: usize,
+    total: usize,
+) {
+    let note = abort_note(status, scanned, total);
+    progress.skip(total - scanned);
+    progress.clear();
+    println!("{}✗ {}{}", status_colour(status), note, reset());
+    write_log_entry(log_file, &format!("Aborted: {}", note));
+}
+
+fn ask_yes_no(question: &str) -> bool {
+    println!("{} (y/n)", question);
+    matches!(read_input(""), Ok(input) if input.to_lowercase() == "y")
+}
 
-    match (target, 80).to_socket_addrs() {
-        Ok(mut iter) => {
-            if let Some(addr) = iter.next() {
-                let note = format!("Resolved Target: {} -> {}", target, addr.ip());
+/// Resolves the target of an interactive scan once, before any port is
+/// probed, and says where a hostname points.
+fn resolve_target(target: &str) -> Option<Arc<Resolution>> {
+    match resolve(target) {
+        Ok(resolution) => {
+            if resolution.source == Source::Proxy {
                 println!(
-                    "{}Resolved {}{}{} to {}{}{}",
-                    YELLOW,
-                    CYAN,
+                    "{}{}{}{} does not resolve here; the proxy will resolve it{}",
+                    cyan(),
                     target,
-                    RESET,
-                    CYAN,
-                    addr.ip(),
-                    RESET
+                    reset(),
+                    yellow(),
+                    reset()
                 );
-                Some(note)
-            } else {
-                let note = format!("Resolution failed for '{}'", target);
+            } else if !is_valid_ip(target) {
                 println!(
-                    "{}Note: '{}' could not be resolved{}",
-                    YELLOW, target, RESET
+                    "{}Resolved {}{}{} to {}{}{}",
+                    yellow(),
+                    cyan(),
+                    target,
+                    reset(),
+                    cyan(),
+                    resolution.ip(),
+                    reset()
                 );
-                thread::sleep(Duration::from_millis(500));
-                Some(note)
             }
+            Some(resolution)
         }
-        Err(_) => {
-            let note = format!("Resolution failed for '{}'", target);
-            println!(
-                "{}Note: '{}' could not be resolved{}",
-                YELLOW, target, RESET
-            );
-            thread::sleep(Duration::from_millis(500));
-            Some(note)
+        Err(e) => {
+            println!("{}{}{}", red(), e, reset());
+            thread::sleep(Duration::from_millis(2000));
+            None
         }
     }
 }
@@ -294,11 +1099,11 @@ fn scanner() {
     println!("Please enter IP address or hostname");
     let ip_input_raw = match read_input("") {
         Ok(input) => {
-            println!("Selected target {}{}{}", CYAN, input.as_str(), RESET);
+            println!("Selected target {}{}{}", cyan(), input.as_str(), reset());
             input
         }
         Err(_) => {
-            println!("{}Failed to read IP address{}", RED, RESET);
+            println!("{}Failed to read IP address{}", red(), reset());
             menu_fallback();
             return;
         }
@@ -306,13 +1111,16 @@ fn scanner() {
 
     let ip_input = ip_input_raw.as_str();
 
-    let resolution_note = resolve_target_note(ip_input);
+    let Some(resolution) = resolve_target(ip_input) else {
+        menu_fallback();
+        return;
+    };
 
     println!("Scan multiple ports? (y/n)");
     let multi_choice = match read_input("") {
         Ok(input) => input,
         Err(_) => {
-            println!("{}Failed to read choice{}", RED, RESET);
+            println!("{}Failed to read choice{}", red(), reset());
             menu_fallback();
             return;
         }
@@ -324,10 +1132,10 @@ fn scanner() {
             Ok(port) => port,
             Err(e) => {
                 if e.kind() == io::ErrorKind::InvalidInput {
-                    println!("{}Invalid start port{}", RED, RESET);
+                    println!("{}Invalid start port{}", red(), reset());
                     thread::sleep(Duration::from_millis(2000));
                 } else {
-                    println!("{}Failed to read start port{}", RED, RESET);
+                    println!("{}Failed to read start port{}", red(), reset());
                 }
                 menu_fallback();
                 return;
@@ -339,10 +1147,10 @@ fn scanner() {
             Ok(port) => port,
             Err(e) => {
                 if e.kind() == io::ErrorKind::InvalidInput {
-                    println!("{}Invalid end port{}", RED, RESET);
+                    println!("{}Invalid end port{}", red(), reset());
                     thread::sleep(Duration::from_millis(2000));
                 } else {
-                    println!("{}Failed to read end port{}", RED, RESET);
+                    println!("{}Failed to read end port{}", red(), reset());
                 }
                 menu_fallback();
                 return;
@@ -350,28 +1158,33 @@ fn scanner() {
         };
 
         if !is_valid_port(start_port) {
-            println!("{}Invalid start port{}", RED, RESET);
+            println!("{}Invalid start port{}", red(), reset());
             menu_fallback();
             return;
         }
 
         if !is_valid_port(end_port) {
-            println!("{}Invalid end port{}", RED, RESET);
+            println!("{}Invalid end port{}", red(), reset());
             menu_fallback();
             return;
         }
 
         if start_port > end_port {
-            println!("{}Start port must be less than end port{}", RED, RESET);
+            println!("{}Start port must be less than end port{}", red(), reset());
             menu_fallback();
             return;
         }
 
+        let range_ports: Vec<u16> = (start_port..=end_port).collect();
+        let inspections = InspectionOptions::ask(&range_ports, false);
+
         let (mut log_file, log_path) = create_log_file("custom_range");
-        write_log_header(&mut log_file, "Custom Range Scan", ip_input);
-        if let Some(note) = &resolution_note {
-            write_log_entry(&mut log_file, note);
-        }
+        let start_time = write_log_header(
+            &mut log_file,
+            "Custom Range Scan",
+            ip_input,
+            Some(&resolution),
+        );
         write_log_entry(
             &mut log_file,
             &format!("Port Range: {}-{}", start_port, end_port),
@@ -379,101 +1192,147 @@ fn scanner() {
 
         println!(
             "\nScanning ports {}{}-{}{} on {}{}{}",
-            YELLOW, start_port, end_port, RESET, CYAN, ip_input, RESET
+            yellow(),
+            start_port,
+            end_port,
+            reset(),
+            cyan(),
+            ip_input,
+            reset()
         );
         println!("This might take a while...\n");
 
         let mut open_ports = Vec::new();
+        let mut inspection_results = InspectionResults::default();
         let total_ports = end_port - start_port + 1;
-        let mut scanned_count = 0;
+        let progress = Progress::new(total_ports as usize);
+        let mut counts = StatusCounts::default();
+        let mut watch = HostWatch::default();
+        let mut ports_scanned = 0usize;
 
         for port in start_port..=end_port {
-            scanned_count += 1;
-
-            let percentage = (scanned_count as f32 / total_ports as f32 * 100.0) as u32;
-            print!(
-                "\rScanning port {} [{}/{}] {}% ",
-                port, scanned_count, total_ports, percentage
-            );
-            print_progress_bar(percentage);
-            io::stdout().flush().unwrap();
-
-            let socket_addr = match resolve_addr(ip_input, port) {
-                Some(addr) => addr,
-                None => {
-                    write_log_entry(&mut log_file, &format!("Port {}: Invalid address", port));
-                    continue;
-                }
-            };
-
-            match TcpStream::connect_timeout(&socket_addr, connect_timeout()) {
-                Ok(_) => {
-                    print!("\r");
-                    print!("{}", " ".repeat(60));
+            progress.tick(&format!("Port {}", port));
+            let result = probe_port(&resolution, port);
+            ports_scanned += 1;
+            let status = result.status;
+            progress.record(status);
+            counts.add(&result);
+
+            match status {
+                PortStatus::Open => {
+                    progress.clear();
                     let service_name = get_service_name(port);
-                    print!(
-                        "\rPort {}{}{} ({}{}{}) is {}{}OPEN{}\n",
-                        YELLOW, port, RESET, CYAN, service_name, RESET, GREEN, BOLD, RESET
+                    println!(
+                        "Port {}{}{} ({}{}{}) is {}{}OPEN{}",
+                        yellow(),
+                        port,
+                        reset(),
+                        cyan(),
+                        service_name,
+                        reset(),
+                        green(),
+                        bold(),
+                        reset()
                     );
                     open_ports.push(port);
                     write_log_entry(
                         &mut log_file,
                         &format!("Port {}: {} - OPEN", port, service_name),
                     );
-
-                    print!(
-                        "Scanning port {} [{}/{}] {}% ",
-                        port, scanned_count, total_ports, percentage
+                    inspect_open_port(
+                        &resolution,
+                        port,
+                        inspections,
+                        &mut log_file,
+                        &mut inspection_results,
                     );
-                    print_progress_bar(percentage);
-                    io::stdout().flush().unwrap();
                 }
-                Err(e) => {
-                    let status = if e.kind() == io::ErrorKind::TimedOut {
-                        "TIMEOUT/FILTERED"
-                    } else {
-                        "CLOSED"
-                    };
-                    write_log_entry(&mut log_file, &format!("Port {}: {}", port, status));
+                _ => {
+                    write_log_entry(
+                        &mut log_file,
+                        &format!("Port {}: {}", port, result.describe()),
+                    );
                 }
             }
+            if watch.give_up(status) {
+                abandon_host(
+                    &progress,
+                    &mut log_file,
+                    status,
+                    ports_scanned,
+                    total_ports as usize,
+                );
+                break;
+            }
         }
-
-        print!("\r");
-        print!("{}", " ".repeat(60));
-        print!("\r");
+        progress.finish();
 
         let elapsed = scan_started.elapsed().as_secs_f32();
+        evaluate_findings(&open_ports, &mut inspection_results);
 
-        println!("\n{}{}Scan complete!{}", GREEN, BOLD, RESET);
-        println!("Found {}{}{} open ports", GREEN, open_ports.len(), RESET);
-        println!("{}Scan took {:.2} seconds{}", CYAN, elapsed, RESET);
+        println!("\n{}{}Scan complete!{}", green(), bold(), reset());
+        println!(
+            "Found {}{}{} open ports",
+            green(),
+            open_ports.len(),
+            reset()
+        );
+        counts.print("");
+        println!("{}Scan took {:.2} seconds{}", cyan(), elapsed, reset());
         if !open_ports.is_empty() {
-            println!("\n{}Open ports{}:", YELLOW, RESET);
+            println!("\n{}Open ports{}:", yellow(), reset());
             for port in open_ports.iter() {
-                let service_name = get_service_name(*port);
+                let service_name = inspection_results.service_label(*port);
                 println!(
                     "  Port {}{:<6}{} {}{:<15}{} {}OPEN{}",
-                    YELLOW, port, RESET, CYAN, service_name, RESET, GREEN, RESET
+                    yellow(),
+                    port,
+                    reset(),
+                    cyan(),
+                    service_name,
+                    reset(),
+                    green(),
+                    reset()
                 );
             }
         }
-
-        write_log_summary(&mut log_file, &open_ports, total_ports as u32, elapsed);
-        println!("\n{}Log saved to {}{}{}", CYAN, BOLD, log_path, RESET);
+        print_findings(&inspection_results.findings);
+
+        counts.log(&mut log_file);
+        let report = ScanReport {
+            scan_type: String::from("Custom Range Scan"),
+            target: ip_input.to_string(),
+            host_name: resolution.ptr.clone().unwrap_or_default(),
+            start_time,
+            end_time: get_timestamp(),
+            total_scanned: ports_scanned as u32,
+            elapsed_secs: elapsed,
+            open_ports,
+            details: inspection_results.details,
+            findings: inspection_results.findings,
+        };
+        write_log_summary(&mut log_file, &report);
+        println!("\n{}Log saved to {}{}{}", cyan(), bold(), log_path, reset());
+        offer_reports(&report, &log_path);
+        notify_scan_complete(
+            &report.scan_type,
+            &report.target,
+            &report.open_ports,
+            &report.findings,
+        );
     } else {
         println!("Please enter Port number");
         let port_input_formatted = match read_u16("") {
             Ok(port) => {
-                println!("Selected Port {}{}{}", CYAN, port, RESET);
+                println!("Selected Port {}{}{}", cyan(), port, reset());
                 port
             }
             Err(e) => {
                 if e.kind() == io::ErrorKind::InvalidInput {
-                    println!("{}Invalid port number{}", RED, RESET);
+                    println!("{}Invalid port number{}", red(), reset());
                     thread::sleep(Duration::from_millis(2000));
                 } else {
-                    println!("{}Failed to read port{}", RED, RESET);
+                    println!("{}Failed to read port{}", red(), reset());
                 }
                 menu_fallback();
                 return;
@@ -481,17 +1340,21 @@ fn scanner() {
         };
 
         if !is_valid_port(port_input_formatted) {
-            println!("{}Invalid port number{}", RED, RESET);
+            println!("{}Invalid port number{}", red(), reset());
             thread::sleep(Duration::from_millis(2000));
             menu_fallback();
             return;
         }
 
+        let inspections = InspectionOptions::ask(&[port_input_formatted], false);
+
         let (mut log_file, log_path) = create_log_file("single_port");
-        write_log_header(&mut log_file, "Single Port Scan", ip_input);
-        if let Some(note) = &resolution_note {
-            write_log_entry(&mut log_file, note);
-        }
+        let start_time = write_log_header(
+            &mut log_file,
+            "Single Port Scan",
+            ip_input,
+            Some(&resolution),
+        );
         write_log_entry(
             &mut log_file,
             &format!("Target Port: {}", port_input_formatted),
@@ -499,7 +1362,12 @@ fn scanner() {
 
         println!(
             "Scanning Port {}{}{} on IP address {}{}{}",
-            YELLOW, port_input_formatted, RESET, CYAN, ip_input, RESET
+            yellow(),
+            port_input_formatted,
+            reset(),
+            cyan(),
+            ip_input,
+            reset()
         );
 
         print!("Scanning... ");
@@ -509,128 +1377,106 @@ fn scanner() {
             thread::sleep(Duration::from_millis(300));
         }
 
-        let socket_addr = match resolve_addr(ip_input, port_input_formatted) {
-            Some(addr) => addr,
-            None => {
-                println!("\n{}Invalid address format{}", RED, RESET);
-                write_log_entry(&mut log_file, "Error: Invalid address format");
-                return;
-            }
-        };
-
         let mut open_ports = Vec::new();
-        match TcpStream::connect_timeout(&socket_addr, connect_timeout()) {
-            Ok(_) => {
+        let mut inspection_results = InspectionResults::default();
+        let result = probe_port(&resolution, port_input_formatted);
+        match result.status {
+            PortStatus::Open => {
                 let service_name = get_service_name(port_input_formatted);
                 println!(
                     " {}{}OPEN{} ({}{}{})",
-                    GREEN, BOLD, RESET, CYAN, service_name, RESET
+                    green(),
+                    bold(),
+                    reset(),
+                    cyan(),
+                    service_name,
+                    reset()
                 );
                 open_ports.push(port_input_formatted);
                 write_log_entry(
                     &mut log_file,
                     &format!("Port {}: {} - OPEN", port_input_formatted, service_name),
                 );
+                inspect_open_port(
+                    &resolution,
+                    port_input_formatted,
+                    inspections,
+                    &mut log_file,
+                    &mut inspection_results,
+                );
             }
-            Err(e) => {
-                if e.kind() == io::ErrorKind::TimedOut {
-                    println!(" {}TIMEOUT/FILTERED{}", YELLOW, RESET);
-                    write_log_entry(
-                        &mut log_file,
-                        &format!("Port {}: TIMEOUT/FILTERED", port_input_formatted),
-                    );
-                } else {
-                    println!(" {}CLOSED{}", RED, RESET);
-                    write_log_entry(
-                        &mut log_file,
-                        &format!("Port {}: CLOSED", port_input_formatted),
-                    );
-                }
+            PortStatus::InvalidAddress => {
+                println!("\n{}Invalid address format{}", red(), reset());
+                write_log_entry(&mut log_file, "Error: Invalid address format");
+                return;
+            }
+            status => {
+                println!(" {}{}{}", status_colour(status), result.describe(), reset());
+                write_log_entry(
+                    &mut log_file,
+                    &format!("Port {}: {}", port_input_formatted, result.describe()),
+                );
             }
         }
 
         let elapsed = scan_started.elapsed().as_secs_f32();
-
-        write_log_summary(&mut log_file, &open_ports, 1, elapsed);
-        println!("{}Scan took {:.2} seconds{}", CYAN, elapsed, RESET);
-        println!("\n{}Log saved to {}{}{}", CYAN, BOLD, log_path, RESET);
+        evaluate_findings(&open_ports, &mut inspection_results);
+        print_findings(&inspection_results.findings);
+
+        let report = ScanReport {
+            scan_type: String::from("Single Port Scan"),
+            target: ip_input.to_string(),
+            host_name: resolution.ptr.clone().unwrap_or_default(),
+            start_time,
+            end_time: get_timestamp(),
+            total_scanned: 1,
+            elapsed_secs: elapsed,
+            open_ports,
+            details: inspection_results.details,
+            findings: inspection_results.findings,
+        };
+        write_log_summary(&mut log_file, &report);
+        println!("{}Scan took {:.2} seconds{}", cyan(), elapsed, reset());
+        println!("\n{}Log saved to {}{}{}", cyan(), bold(), log_path, reset());
+        offer_reports(&report, &log_path);
+        notify_scan_complete(
+            &report.scan_type,
+            &report.target,
+            &report.open_ports,
+            &report.findings,
+        );
     }
 
     press_enter_to_continue();
 }
 
-#[derive(Debug)]
-enum ScanProfile {
-    Quick,
-    Web,
-    Database,
-    Full,
-}
-
-impl ScanProfile {
-    fn get_ports(&self) -> Vec<u16> {
-        match self {
-            ScanProfile::Quick => vec![
-                21, 22, 23, 25, 53, 80, 110, 143, 443, 445, 993, 995, 1723, 3306, 3389, 5900, 8080,
-            ],
-            ScanProfile::Web => vec![
-                80, 443, 3000, 3001, 4200, 4443, 5000, 5001, 8000, 8008, 8080, 8081, 8088, 8443,
-                8888, 9000,
-            ],
-            ScanProfile::Database => vec![
-                1433, 1521, 3306, 5432, 5984, 6379, 7000, 7001, 8086, 9042, 9200, 11211, 27017,
-                50000,
-            ],
-            ScanProfile::Full => vec![
-                21, 22, 23, 25, 53, 67, 68, 80, 110, 111, 123, 135, 139, 143, 161, 389, 443, 445,
-                465, 514, 587, 636, 993, 995, 1080, 1194, 1433, 1521, 1723, 1883, 3000, 3128, 3306,
-                3389, 5060, 5432, 5672, 5900, 5984, 5985, 6379, 7000, 8080, 8086, 8443, 8888, 9092,
-                9200, 10000, 11211, 15672, 27017,
-            ],
-        }
-    }
-
-    fn get_name(&self) -> &str {
-        match self {
-            ScanProfile::Quick => "Quick Scan",
-            ScanProfile::Web => "Web Services",
-            ScanProfile::Database => "Database Services",
-            ScanProfile::Full => "Full Common Ports",
-        }
-    }
-
-    fn get_log_name(&self) -> &str {
-        match self {
-            ScanProfile::Quick => "profile_quick",
-            ScanProfile::Web => "profile_web",
-            ScanProfile::Database => "profile_database",
-            ScanProfile::Full => "profile_full",
-        }
-    }
-}
-
 fn profile_scan() {
     clear_screen();
     let scan_started = Instant::now();
 
     println!(
         "{}{}=== Profile-Based Port Scanner ==={}\n",
-        YELLOW, BOLD, RESET
+        yellow(),
+        bold(),
+        reset()
     );
 
     println!("Please enter IP address or hostname:");
     let ip_input = match read_input("") {
         Ok(input) => input,
         Err(_) => {
-            println!("{}Failed to read IP address{}", RED, RESET);
+            println!("{}Failed to read IP address{}", red(), reset());
             menu_fallback();
             return;
         }
     };
 
-    let resolution_note = resolve_target_note(ip_input.as_str());
+    let Some(resolution) = resolve_target(ip_input.as_str()) else {
+        menu_fallback();
+        return;
+    };
 
-    println!("\n{}Select scan profile{}:", YELLOW, RESET);
+    println!("\n{}Select scan profile{}:", yellow(), reset());
     println!(
         "1. Quick Scan ({} ports)",
         ScanProfile::Quick.get_ports().len()
@@ -653,7 +1499,7 @@ fn profile_scan() {
     let profile_choice = match read_input("") {
         Ok(input) => input,
         Err(_) => {
-            println!("{}Failed to read choice{}", RED, RESET);
+            println!("{}Failed to read choice{}", red(), reset());
             menu_fallback();
             return;
         }
@@ -665,17 +1511,23 @@ fn profile_scan() {
         "3" => ScanProfile::Database,
         "4" => ScanProfile::Full,
         _ => {
-            println!("{}Invalid choice{}", RED, RESET);
+            println!("{}Invalid choice{}", red(), reset());
             menu_fallback();
             return;
         }
     };
 
+    println!();
+    let inspections =
+        InspectionOptions::ask(&profile.get_ports(), matches!(profile, ScanProfile::Web));
+
     let (mut log_file, log_path) = create_log_file(profile.get_log_name());
-    write_log_header(&mut log_file, profile.get_name(), ip_input.as_str());
-    if let Some(note) = &resolution_note {
-        write_log_entry(&mut log_file, note);
-    }
+    let start_time = write_log_header(
+        &mut log_file,
+        profile.get_name(),
+        ip_input.as_str(),
+        Some(&resolution),
+    );
 
     let ports_to_scan = profile.get_ports();
     let total_ports = ports_to_scan.len();
@@ -689,42 +1541,50 @@ fn profile_scan() {
 
     println!(
         "\n{}{}{} - Scanning {}{}{} ports on {}{}{}",
-        YELLOW,
+        yellow(),
         profile.get_name(),
-        RESET,
-        CYAN,
+        reset(),
+        cyan(),
         total_ports,
-        RESET,
-        CYAN,
+        reset(),
+        cyan(),
         ip_input,
-        RESET
+        reset()
     );
 
     let mut open_ports = Vec::new();
+    let mut inspection_results = InspectionResults::default();
+    let progress = Progress::new(total_ports);
+    let mut counts = StatusCounts::default();
+    let mut watch = HostWatch::default();
+    let mut ports_scanned = 0usize;
 
-    for (index, port) in ports_to_scan.iter().enumerate() {
-        let percentage = ((index + 1) as f32 / total_ports as f32 * 100.0) as u32;
+    for port in &ports_to_scan {
         let service_name = get_service_name(*port);
 
-        print!("\rScanning {} ({})... ", service_name, port);
-        io::stdout().flush().unwrap();
+        progress.tick(&format!("{} ({})", service_name, port));
+        let result = probe_port(&resolution, *port);
+        ports_scanned += 1;
+        let status = result.status;
+        progress.record(status);
+        counts.add(&result);
 
-        match scan_port(ip_input.as_str(), *port) {
+        match status {
             PortStatus::Open => {
-                print!("\r\x1b[2K");
+                progress.clear();
                 println!(
                     "{}✓{} {}{}{} ({}{}{}) - {}{}OPEN{}",
-                    GREEN,
-                    RESET,
-                    CYAN,
+                    green(),
+                    reset(),
+                    cyan(),
                     service_name,
-                    RESET,
-                    YELLOW,
+                    reset(),
+                    yellow(),
                     port,
-                    RESET,
-                    GREEN,
-                    BOLD,
-                    RESET
+                    reset(),
+                    green(),
+                    bold(),
+                    reset()
                 );
                 open_ports.push(*port);
                 write_log_entry(
@@ -732,99 +1592,887 @@ fn profile_scan() {
                     &format!("Port {}: {} - OPEN", port, service_name),
                 );
 
-                print!("Progress: [{}/{}] {}% ", index + 1, total_ports, percentage);
-                print_progress_bar(percentage);
-                io::stdout().flush().unwrap();
-            }
-            PortStatus::TimeoutFiltered => {
-                write_log_entry(
-                    &mut log_file,
-                    &format!("Port {}: {} - TIMEOUT/FILTERED", port, service_name),
-                );
-                print!(
-                    "\rProgress: [{}/{}] {}% ",
-                    index + 1,
-                    total_ports,
-                    percentage
-                );
-                print_progress_bar(percentage);
-                io::stdout().flush().unwrap();
-            }
-            PortStatus::Closed => {
-                write_log_entry(
+                inspect_open_port(
+                    &resolution,
+                    *port,
+                    inspections,
                     &mut log_file,
-                    &format!("Port {}: {} - CLOSED", port, service_name),
-                );
-                print!(
-                    "\rProgress: [{}/{}] {}% ",
-                    index + 1,
-                    total_ports,
-                    percentage
+                    &mut inspection_results,
                 );
-                print_progress_bar(percentage);
-                io::stdout().flush().unwrap();
             }
             PortStatus::InvalidAddress => {
                 write_log_entry(&mut log_file, &format!("Port {}: Invalid address", port));
                 continue;
             }
+            _ => {
+                write_log_entry(
+                    &mut log_file,
+                    &format!("Port {}: {} - {}", port, service_name, result.describe()),
+                );
+            }
+        }
+        if watch.give_up(status) {
+            abandon_host(&progress, &mut log_file, status, ports_scanned, total_ports);
+            break;
         }
     }
-
-    print!("\r\x1b[2K");
+    progress.finish();
 
     let elapsed = scan_started.elapsed().as_secs_f32();
+    evaluate_findings(&open_ports, &mut inspection_results);
 
     println!(
         "\n{}{}{} Scan Complete!{}",
-        GREEN,
-        BOLD,
+        green(),
+        bold(),
         profile.get_name(),
-        RESET
+        reset()
     );
     println!("{}", "━".repeat(33));
     println!(
         "Found {}{}{} open ports out of {} scanned",
-        GREEN,
+        green(),
         open_ports.len(),
-        RESET,
-        total_ports
+        reset(),
+        ports_scanned
     );
-    println!("{}Scan took {:.2} seconds{}", CYAN, elapsed, RESET);
+    counts.print("");
+    println!("{}Scan took {:.2} seconds{}", cyan(), elapsed, reset());
 
     if !open_ports.is_empty() {
-        println!("\n{}Summary of open services{}:", YELLOW, RESET);
+        println!("\n{}Summary of open services{}:", yellow(), reset());
         for port in &open_ports {
-            let service = get_service_name(*port);
+            let service = inspection_results.service_label(*port);
             println!(
                 "  {}•{} {}{:<15}{} on port {}{}{}",
-                GREEN, RESET, CYAN, service, RESET, YELLOW, port, RESET
+                green(),
+                reset(),
+                cyan(),
+                service,
+                reset(),
+                yellow(),
+                port,
+                reset()
             );
         }
     } else {
-        println!("\n{}No open ports found.{}", YELLOW, RESET);
+        println!("\n{}No open ports found.{}", yellow(), reset());
     }
 
-    write_log_summary(&mut log_file, &open_ports, total_ports as u32, elapsed);
-    println!("\n{}Log saved to {}{}{}", CYAN, BOLD, log_path, RESET);
+    if !inspection_results.details.is_empty() {
+        println!("\n{}Service details{}:", yellow(), reset());
+        for (port, details) in &inspection_results.details {
+            println!("  {}{:<6}{} {}", yellow(), port, reset(), details);
+        }
+    }
+
+    print_findings(&inspection_results.findings);
+
+    counts.log(&mut log_file);
+    let report = ScanReport {
+        scan_type: profile.get_name().to_string(),
+        target: ip_input.clone(),
+        host_name: resolution.ptr.clone().unwrap_or_default(),
+        start_time,
+        end_time: get_timestamp(),
+        total_scanned: ports_scanned as u32,
+        elapsed_secs: elapsed,
+        open_ports,
+        details: inspection_results.details,
+        findings: inspection_results.findings,
+    };
+    write_log_summary(&mut log_file, &report);
+    println!("\n{}Log saved to {}{}{}", cyan(), bold(), log_path, reset());
+    offer_reports(&report, &log_path);
+    notify_scan_complete(
+        &report.scan_type,
+        &report.target,
+        &report.open_ports,
+        &report.findings,
+    );
 
     press_enter_to_continue();
 }
 
-fn print_progress_bar(percentage: u32) {
-    let bar_width: usize = 20;
-    let filled = bar_width * percentage as usize / 100;
-    let empty = bar_width.saturating_sub(filled);
+/// `vonogs scan`: probes every target without prompts. Quiet and grepable
+/// output go to stdout with nothing else mixed in; warnings go to stderr.
+/// Each host still gets its usual log in `scan_logs/`.
+fn scan_command(targets: &[String], scan_type: &str, ports: &[u16], output: ScanOutput) -> i32 {
+    create_logs_directory();
+    let (rules, warnings) = load_rules();
+    for warning in warnings {
+        eprintln!("{}Warning: {}{}", yellow(), warning, reset());
+    }
+
+    let started = get_timestamp();
+    let scan_started = Instant::now();
+    match output {
+        ScanOutput::Normal => println!(
+            "{}{}=== {}: {} ports on {} host(s) ==={}",
+            yellow(),
+            bold(),
+            scan_type,
+            ports.len(),
+            targets.len(),
+            reset()
+        ),
+        ScanOutput::Quiet => {}
+        ScanOutput::Grepable => println!(
+            "# vonogs {} scan initiated {} as: {}",
+            scanner_info().version,
+            started,
+            std::env::args().collect::<Vec<_>>().join(" ")
+        ),
+    }
+
+    let progress = Progress::new(ports.len() * targets.len());
+    let mut unresolved = 0;
+    let mut down = 0;
+    for target in targets {
+        let mut host = HostScan::new(target, ports);
+        let host_started = Instant::now();
+        if output == ScanOutput::Normal {
+            println!(
+                "\nScanning {}{}{} ({} ports)",
+                cyan(),
+                target,
+                reset(),
+                ports.len()
+            );
+        }
+
+        match resolve(target) {
+            Err(message) => {
+                host.error = Some(String::from("could not be resolved"));
+                unresolved += 1;
+                progress.skip(ports.len());
+                match output {
+                    ScanOutput::Normal => println!("  {}✗ {}{}", red(), message, reset()),
+                    _ => eprintln!("Warning: {}", message),
+                }
+            }
+            Ok(resolution) => {
+                host.results = scan_ports(&resolution, ports, |_, result| {
+                    if output == ScanOutput::Normal {
+                        progress.record(result.status);
+                        progress.tick(target);
+                    }
+                    if result.status != PortStatus::Open {
+                        return;
+                    }
+                    match output {
+                        ScanOutput::Normal => {
+                            progress.clear();
+                            println!(
+                                "  Port {}{}{} ({}{}{}) is {}{}OPEN{}",
+                                yellow(),
+                                result.port,
+                                reset(),
+                                cyan(),
+                                get_service_name(result.port),
+                                reset(),
+                                green(),
+                                bold(),
+                                reset()
+                            );
+                        }
+                        ScanOutput::Quiet => {
+                            if target.contains(':') {
+                                println!("[{}]:{}", target, result.port);
+                            } else {
+                                println!("{}:{}", target, result.port);
+                            }
+                            let _ = io::stdout().flush();
+                        }
+                        ScanOutput::Grepable => {}
+                    }
+                });
+                let aborted = host_abort(&host.results, ports.len());
+                if let Some(status) = aborted {
+                    let note = abort_note(status, host.results.len(), ports.len());
+                    progress.skip(ports.len() - host.results.len());
+                    down += 1;
+                    match output {
+                        ScanOutput::Normal => {
+                            progress.clear();
+                            println!("  {}✗ {}{}", status_colour(status), note, reset());
+                        }
+                        _ => eprintln!("Warning: '{}': {}", target, note),
+                    }
+                    host.error = Some(note);
+                }
+                if output == ScanOutput::Grepable {
+                    let up = aborted.is_none();
+                    println!("{}", grepable::status_line(&resolution, up));
+                    println!("{}", grepable::host_line(&resolution, &host.results));
+                }
+                host.resolution = Some(resolution);
+            }
+        }
+        host.elapsed_secs = host_started.elapsed().as_secs_f32();
+        host.finished = true;
+        progress.clear();
 
-    print!("[");
-    print!("{}{}{}", GREEN, "=".repeat(filled), RESET);
-    if filled < bar_width {
-        print!("{}>{}", YELLOW, RESET);
-        if empty > 1 {
-            print!("{}", " ".repeat(empty - 1));
+        let (log_path, report) = save_host_log("cli", scan_type, &host, &rules);
+        if output == ScanOutput::Normal {
+            if host.error.is_none() {
+                println!(
+                    "  {}{} open{}, {} finding(s) · {:.2} seconds · log {}{}{}",
+                    green(),
+                    report.open_ports.len(),
+                    reset(),
+                    report.findings.len(),
+                    report.elapsed_secs,
+                    bold(),
+                    log_path,
+                    reset()
+                );
+            }
+            StatusCounts::tally(&host.results).print("  ");
+            print_findings(&report.findings);
         }
     }
-    print!("]");
+    progress.finish();
+
+    if output == ScanOutput::Grepable {
+        println!(
+            "# vonogs done at {} -- {} host(s) scanned ({} up) in {:.2} seconds",
+            get_timestamp(),
+            targets.len() - unresolved,
+            targets.len() - unresolved - down,
+            scan_started.elapsed().as_secs_f32()
+        );
+    }
+    if unresolved + down > 0 {
+        2
+    } else {
+        0
+    }
+}
+
+/// Scans every host in the policy file and returns the process exit code:
+/// 0 when all hosts comply, 1 on violations, 2 when the check could not run.
+fn policy_check(policy_path: &str, sarif_path: Option<&str>, junit_path: Option<&str>) -> i32 {
+    let hosts = match fs::read_to_string(policy_path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| parse_policy(&contents))
+    {
+        Ok(hosts) => hosts,
+        Err(message) => {
+            eprintln!(
+                "{}Error: could not load policy '{}': {}{}",
+                red(),
+                policy_path,
+                message,
+                reset()
+            );
+            return 2;
+        }
+    };
+
+    create_logs_directory();
+    let scan_started = Instant::now();
+    let (mut log_file, log_path) = create_log_file("policy");
+    write_log_header(&mut log_file, "Policy Check", policy_path, None);
+
+    println!(
+        "{}{}=== Policy Check: {} ==={}",
+        yellow(),
+        bold(),
+        policy_path,
+        reset()
+    );
+
+    let mut violations = Vec::new();
+    let mut unusable_targets = Vec::new();
+    let mut suites = Vec::new();
+    let mut total_scanned = 0u32;
+    let progress = Progress::new(hosts.iter().map(|h| h.ports_to_scan().len()).sum());
+
+    for host in &hosts {
+        let host_started = Instant::now();
+        let ports = host.ports_to_scan();
+        println!(
+            "\nChecking {}{}{} ({} ports)",
+            cyan(),
+            host.target,
+            reset(),
+            ports.len()
+        );
+        write_log_entry(
+            &mut log_file,
+            &format!("\nHost: {} ({} ports)", host.target, ports.len()),
+        );
+
+        let resolution = match resolve(&host.target) {
+            Ok(resolution) => resolution,
+            Err(_) => {
+                println!(
+                    "  {}✗ '{}' could not be resolved{}",
+                    red(),
+                    host.target,
+                    reset()
+                );
+                write_log_entry(
+                    &mut log_file,
+                    &format!("ERROR {}: could not be resolved", host.target),
+                );
+                unusable_targets.push(host.target.clone());
+                progress.skip(ports.len());
+                suites.push(HostSuite {
+                    target: host.target.clone(),
+                    checks: Vec::new(),
+                    error: Some(String::from("could not be resolved")),
+                    elapsed_secs: host_started.elapsed().as_secs_f32(),
+                });
+                continue;
+            }
+        };
+        for line in resolution.log_lines() {
+            write_log_entry(&mut log_file, &line);
+        }
+
+        let results = scan_ports(&resolution, &ports, |_, result| {
+            progress.record(result.status);
+            progress.tick(&host.target);
+        });
+        progress.clear();
+        total_scanned += results.len() as u32;
+
+        if let Some(status) = host_abort(&results, ports.len()) {
+            let note = abort_note(status, results.len(), ports.len());
+            println!("  {}✗ {}{}", status_colour(status), note, reset());
+            write_log_entry(&mut log_file, &format!("ERROR {}: {}", host.target, note));
+            unusable_targets.push(host.target.clone());
+            progress.skip(ports.len() - results.len());
+            suites.push(HostSuite {
+                target: host.target.clone(),
+                checks: Vec::new(),
+                error: Some(note),
+                elapsed_secs: host_started.elapsed().as_secs_f32(),
+            });
+            continue;
+        }
+
+        let checks = evaluate_policy(host, &results);
+        for check in &checks {
+            let verdict = if check.passed { "PASS" } else { "FAIL" };
+            write_log_entry(
+                &mut log_file,
+                &format!("{} {}: {}", verdict, check.target, check.message()),
+            );
+
+            if !check.passed {
+                println!("  {}✗ {}{}", red(), check.message(), reset());
+                violations.push(check.clone());
+            } else if check.expectation == Expectation::MustBeOpen
+                || check.status == PortStatus::Open
+            {
+                println!("  {}✓{} {}", green(), reset(), check.message());
+            }
+        }
+        suites.push(HostSuite {
+            target: host.target.clone(),
+            checks,
+            error: None,
+            elapsed_secs: host_started.elapsed().as_secs_f32(),
+        });
+    }
+    progress.finish();
+
+    let elapsed = scan_started.elapsed().as_secs_f32();
+    let summary = format!(
+        "\n=================================\n\
+         Policy Summary\n\
+         =================================\n\
+         Hosts Checked: {}\n\
+         Total Ports Scanned: {}\n\
+         Violations: {}\n\
+         Unusable Targets: {}\n\
+         Scan Duration: {:.2} seconds",
+        hosts.len(),
+        total_scanned,
+        violations.len(),
+        unusable_targets.len(),
+        elapsed
+    );
+    write_log_entry(&mut log_file, &summary);
+    if !violations.is_empty() {
+        write_log_entry(&mut log_file, "\nViolations:");
+        for check in &violations {
+            write_log_entry(
+                &mut log_file,
+                &format!("  {}: {}", check.target, check.message()),
+            );
+        }
+    }
+    write_log_entry(&mut log_file, &format!("\nEnd Time: {}", get_timestamp()));
+
+    if let Some(sarif_path) = sarif_path {
+        let findings: Vec<(String, Finding)> = violations
+            .iter()
+            .filter_map(|check| check.finding().map(|f| (check.target.clone(), f)))
+            .collect();
+        let entries: Vec<(&str, &Finding)> =
+            findings.iter().map(|(t, f)| (t.as_str(), f)).collect();
+        match fs::write(sarif_path, render_sarif(&entries)) {
+            Ok(()) => println!(
+                "{}SARIF saved to {}{}{}",
+                cyan(),
+                bold(),
+                sarif_path,
+                reset()
+            ),
+            Err(e) => {
+                eprintln!(
+                    "{}Error: could not write {}: {}{}",
+                    red(),
+                    sarif_path,
+                    e,
+                    reset()
+                );
+                return 2;
+            }
+        }
+    }
+
+    if let Some(junit_path) = junit_path {
+        match fs::write(junit_path, render_junit(policy_path, &suites)) {
+            Ok(()) => println!(
+                "{}JUnit XML saved to {}{}{}",
+                cyan(),
+                bold(),
+                junit_path,
+                reset()
+            ),
+            Err(e) => {
+                eprintln!(
+                    "{}Error: could not write {}: {}{}",
+                    red(),
+                    junit_path,
+                    e,
+                    reset()
+                );
+                return 2;
+            }
+        }
+    }
+
+    let exit_code = policy_exit_code(unusable_targets.len(), violations.len());
+    match exit_code {
+        2 => println!(
+            "\n{}{}Policy check incomplete:{} {} target(s) could not be resolved or reached",
+            red(),
+            bold(),
+            reset(),
+            unusable_targets.len()
+        ),
+        1 => println!(
+            "\n{}{}Policy FAILED:{} {} violation(s)",
+            red(),
+            bold(),
+            reset(),
+            violations.len()
+        ),
+        _ => println!("\n{}{}Policy PASSED{}", green(), bold(), reset()),
+    }
+    println!("{}Log saved to {}{}{}", cyan(), bold(), log_path, reset());
+    exit_code
+}
+
+/// Converts saved scan logs into a single HTML or Markdown report.
+fn build_report(log_paths: &[String], output: Option<String>, format: ReportFormat) -> i32 {
+    let mut reports = Vec::new();
+    for path in log_paths {
+        match fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| parse_log(&contents))
+        {
+            Ok(report) => reports.push(report),
+            Err(message) => {
+                eprintln!("{}Error: {}: {}{}", red(), path, message, reset());
+                return 2;
+            }
+        }
+    }
+
+    let output = output.unwrap_or_else(|| match log_paths {
+        [single] if single.ends_with(".log") => {
+            format!("{}.{}", single.trim_end_matches(".log"), format.extension())
+        }
+        _ => {
+            create_logs_directory();
+            format!(
+                "scan_logs/report_{}.{}",
+                get_timestamp(),
+                format.extension()
+            )
+        }
+    });
+    match fs::write(&output, format.render(&reports, &get_timestamp())) {
+        Ok(()) => {
+            println!(
+                "{}Report for {} scan(s) saved to {}{}{}",
+                cyan(),
+                reports.len(),
+                bold(),
+                output,
+                reset()
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!(
+                "{}Error: could not write {}: {}{}",
+                red(),
+                output,
+                e,
+                reset()
+            );
+            2
+        }
+    }
+}
+
+fn serve_api(listen: &str) -> i32 {
+    println!(
+        "{}{}=== Vonogs API listening on http://{} ==={}",
+        yellow(),
+        bold(),
+        listen,
+        reset()
+    );
+    println!("  POST   /scans              start a scan");
+    println!("  GET    /scans/{{id}}         status and progress");
+    println!("  GET    /scans/{{id}}/results structured results");
+    println!("  DELETE /scans/{{id}}         cancel a scan\n");
+
+    match server::serve(listen, get_timestamp) {
+        Ok(()) => 0,
+        Err(message) => {
+            eprintln!("{}Error: {}{}", red(), message, reset());
+            2
+        }
+    }
+}
+
+fn deliver_webhook(config: &WebhookConfig, event: &str, body: &str) -> bool {
+    match webhook::send(config, event, body) {
+        Ok(delivery) => {
+            eprintln!(
+                "{}Webhook {} delivered (HTTP {}, {} attempt(s)){}",
+                cyan(),
+                event,
+                delivery.status_code,
+                delivery.attempts,
+                reset()
+            );
+            true
+        }
+        Err(message) => {
+            eprintln!(
+                "{}Warning: webhook {} to {} failed: {}{}",
+                yellow(),
+                event,
+                config.url,
+                message,
+                reset()
+            );
+            false
+        }
+    }
+}
+
+fn load_webhook_config() -> Option<WebhookConfig> {
+    match webhook_config() {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!(
+                "{}Warning: webhook disabled: {}{}",
+                yellow(),
+                message,
+                reset()
+            );
+            None
+        }
+    }
+}
+
+/// Sends `scan_completed`, plus `findings_detected` when any finding reaches
+/// the configured severity.
+fn notify_scan_complete(scan_type: &str, target: &str, open_ports: &[u16], findings: &[Finding]) {
+    let config = match load_webhook_config() {
+        Some(config) => config,
+        None => return,
+    };
+
+    let timestamp = get_timestamp();
+    let body = scan_payload(
+        "scan_completed",
+        &timestamp,
+        scan_type,
+        target,
+        open_ports,
+        findings,
+    );
+    deliver_webhook(&config, "scan_completed", &body);
+
+    let serious: Vec<Finding> = findings
+        .iter()
+        .filter(|f| f.severity >= config.min_severity)
+        .cloned()
+        .collect();
+    if !serious.is_empty() {
+        let body = scan_payload(
+            "findings_detected",
+            &timestamp,
+            scan_type,
+            target,
+            open_ports,
+            &serious,
+        );
+        deliver_webhook(&config, "findings_detected", &body);
+    }
+}
+
+fn webhook_test() -> i32 {
+    let config = match webhook_config() {
+        Ok(Some(config)) => config,
+        Ok(None) => {
+            eprintln!("{}Error: VONOGS_WEBHOOK_URL is not set{}", red(), reset());
+            return 2;
+        }
+        Err(message) => {
+            eprintln!("{}Error: {}{}", red(), message, reset());
+            return 2;
+        }
+    };
+
+    println!("Sending test event to {}{}{}", cyan(), config.url, reset());
+    let body = scan_payload("test", &get_timestamp(), "Webhook Test", "", &[], &[]);
+    if deliver_webhook(&config, "test", &body) {
+        0
+    } else {
+        1
+    }
+}
+
+fn watch_mode(config_path: &str) -> i32 {
+    let config = match fs::read_to_string(config_path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| parse_watch_config(&contents))
+    {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!(
+                "{}Error: could not load watch config '{}': {}{}",
+                red(),
+                config_path,
+                message,
+                reset()
+            );
+            return 2;
+        }
+    };
+
+    create_logs_directory();
+    let log = RotatingLog::new(&config.log_path, config.log_max_bytes, config.log_keep);
+    let write_log = |line: &str| {
+        if let Err(message) = log.write_line(&format!("{} {}", get_timestamp(), line)) {
+            eprintln!("{}Warning: {}{}", yellow(), message, reset());
+        }
+    };
+
+    println!(
+        "{}{}=== Watching {} host(s) every {}s ==={}",
+        yellow(),
+        bold(),
+        config.targets.len(),
+        config.interval.as_secs(),
+        reset()
+    );
+    println!("{}Log: {}{}{}", cyan(), bold(), log.path(), reset());
+    write_log(&format!(
+        "watch started: {} host(s), interval {}s",
+        config.targets.len(),
+        config.interval.as_secs()
+    ));
+
+    let metrics: SharedMetrics = Arc::new(Mutex::new(Metrics::default()));
+    if let Some(listen) = &config.metrics_listen {
+        if let Err(message) = metrics::serve(listen, Arc::clone(&metrics)) {
+            eprintln!("{}Error: {}{}", red(), message, reset());
+            return 2;
+        }
+        println!(
+            "{}Metrics: {}http://{}/metrics{}",
+            cyan(),
+            bold(),
+            listen,
+            reset()
+        );
+        write_log(&format!("metrics listening on {}", listen));
+    }
+    let update_metrics = |update: &dyn Fn(&mut Metrics)| {
+        if let Ok(mut metrics) = metrics.lock() {
+            update(&mut metrics);
+        }
+    };
+
+    let webhook = load_webhook_config();
+    let (rules, _) = load_rules();
+    let timeout = connect_timeout();
+    let mut states: HashMap<String, HostState> = HashMap::new();
+    let mut addresses: HashMap<String, (Vec<IpAddr>, Option<String>)> = HashMap::new();
+    let mut cycle = 0u64;
+
+    loop {
+        cycle += 1;
+        let cycle_started = Instant::now();
+        let mut change_count = 0;
+        let mut open_count = 0;
+        let progress = Progress::new(config.targets.iter().map(|h| h.ports_to_scan().len()).sum());
+
+        for host in &config.targets {
+            let ports = host.ports_to_scan();
+            let resolution = match resolve(&host.target) {
+                Ok(resolution) => resolution,
+                Err(_) => {
+                    progress.clear();
+                    println!(
+                        "{}[{}] {}: could not be resolved, keeping last state{}",
+                        yellow(),
+                        get_timestamp(),
+                        host.target,
+                        reset()
+                    );
+                    write_log(&format!("{}: could not be resolved", host.target));
+                    progress.skip(ports.len());
+                    update_metrics(&|m| m.record_down(&host.target));
+                    continue;
+                }
+            };
+
+            let lines = resolution.log_lines();
+            let seen = (resolution.addrs.clone(), resolution.ptr.clone());
+            if !lines.is_empty() && addresses.get(&host.target) != Some(&seen) {
+                let note = format!("{}: {}", host.target, lines.join(", "));
+                progress.clear();
+                println!("{}[{}]{} {}", cyan(), get_timestamp(), reset(), note);
+                write_log(&note);
+                addresses.insert(host.target.clone(), seen);
+            }
+
+            let host_started = Instant::now();
+            let results = scan_ports(&resolution, &ports, |_, result| {
+                progress.record(result.status);
+                progress.tick(&host.target);
+            });
+            progress.clear();
+            if let Some(status) = host_abort(&results, ports.len()) {
+                let note = abort_note(status, results.len(), ports.len());
+                println!(
+                    "{}[{}] {}: {}, keeping last state{}",
+                    status_colour(status),
+                    get_timestamp(),
+                    host.target,
+                    note,
+                    reset()
+                );
+                write_log(&format!("{}: {}", host.target, note));
+                progress.skip(ports.len() - results.len());
+                update_metrics(&|m| m.record_down(&host.target));
+                continue;
+            }
+            let duration = host_started.elapsed().as_secs_f32();
+            update_metrics(&|m| m.record_scan(&host.target, &results, duration));
+            let previous = states.get(&host.target);
+            let mut current = HostState::new();
+            for port in open_ports(&results) {
+                let last_fingerprint =
+                    previous.and_then(|state| state.get(&port).cloned().flatten());
+                let port_fingerprint = if config.fingerprint {
+                    fingerprint(resolution.socket_addr(port), &host.target, timeout)
+                        .or(last_fingerprint)
+                } else {
+                    None
+                };
+                current.insert(port, port_fingerprint);
+            }
+            open_count += current.len();
+
+            match previous {
+                None => {
+                    let ports: Vec<String> = current.keys().map(|p| p.to_string()).collect();
+                    let baseline = format!(
+                        "{}: baseline with {} open port(s) [{}]",
+                        host.target,
+                        current.len(),
+                        ports.join(", ")
+                    );
+                    println!("{}[{}]{} {}", cyan(), get_timestamp(), reset(), baseline);
+                    write_log(&baseline);
+                }
+                Some(previous) => {
+                    let events = diff_states(&host.target, previous, &current);
+                    update_metrics(&|m| m.record_changes(&host.target, events.len()));
+                    for event in &events {
+                        change_count += 1;
+                        let colour = match event.kind {
+                            ChangeKind::Opened => red(),
+                            ChangeKind::Closed => green(),
+                            ChangeKind::ServiceChanged { .. } => yellow(),
+                        };
+                        let timestamp = get_timestamp();
+                        println!(
+                            "{}[{}] {}: {}{}",
+                            colour,
+                            timestamp,
+                            event.target,
+                            event.message(),
+                            reset()
+                        );
+                        write_log(&format!("CHANGE {}: {}", event.target, event.message()));
+                        if let Some(events_file) = &config.events_file {
+                            if let Err(message) =
+                                append_line(events_file, &event.to_json(&timestamp))
+                            {
+                                eprintln!("{}Warning: {}{}", yellow(), message, reset());
+                            }
+                        }
+                    }
+                    if let (Some(webhook), false) = (&webhook, events.is_empty()) {
+                        let ports: Vec<u16> = current.keys().copied().collect();
+                        let mut findings = evaluate_rules(&rules, &ports);
+                        sort_findings(&mut findings);
+                        let body = change_payload(
+                            &get_timestamp(),
+                            &host.target,
+                            &ports,
+                            &events,
+                            &findings,
+                        );
+                        deliver_webhook(webhook, "watch_change", &body);
+                    }
+                }
+            }
+            states.insert(host.target.clone(), current);
+        }
+        progress.finish();
+
+        let summary = format!(
+            "cycle {} finished in {:.2}s: {} open port(s), {} change(s)",
+            cycle,
+            cycle_started.elapsed().as_secs_f32(),
+            open_count,
+            change_count
+        );
+        println!("[{}] {}", get_timestamp(), summary);
+        write_log(&summary);
+        update_metrics(&|m| m.cycles += 1);
+
+        thread::sleep(config.interval.saturating_sub(cycle_started.elapsed()));
+    }
 }
 
 enum MenuItem {
@@ -848,16 +2496,24 @@ struct ScannerBasicInfo {
     version: f32,
 }
 
+fn scanner_info() -> ScannerBasicInfo {
+    ScannerBasicInfo {
+        name: String::from("Vonogs Scanner"),
+        version: 0.3,
+    }
+}
+
 fn print_menu_items() {
     clear_screen();
 
-    let my_scanner: ScannerBasicInfo = ScannerBasicInfo {
-        name: String::from("Vonogs Scanner"),
-        version: 0.3,
-    };
+    let my_scanner: ScannerBasicInfo = scanner_info();
     println!(
         "{}{}{} v{}{}",
-        CYAN, BOLD, my_scanner.name, my_scanner.version, RESET
+        cyan(),
+        bold(),
+        my_scanner.name,
+        my_scanner.version,
+        reset()
     );
     println!("====================");
 
@@ -872,7 +2528,24 @@ fn print_menu_items() {
     io::stdout().flush().unwrap();
 }
 
+/// Shows what is running until `clear_status`; plain output skips it.
+fn show_status(message: &str) {
+    if color::redraw() {
+        print!("{}", message);
+        io::stdout().flush().unwrap();
+    }
+}
+
+fn clear_status() {
+    if color::redraw() {
+        print!("\r\x1b[2K");
+    }
+}
+
 fn clear_screen() {
+    if !color::redraw() {
+        return;
+    }
     if cfg!(target_os = "windows") {
         let _ = std::process::Command::new("cmd")
             .args(["/c", "cls"])
@@ -884,23 +2557,23 @@ fn clear_screen() {
 
 fn menu_fallback() {
     clear_screen();
-    println!("{}Please select option from the menu.{}", YELLOW, RESET);
+    println!("{}Please select option from the menu.{}", yellow(), reset());
     thread::sleep(Duration::from_millis(2000));
     print_menu_items();
 }
 
 fn end_program() {
-    println!("\n{}Thank you for using Vonogs Scanner!{}", CYAN, RESET);
-    println!("{}Goodbye!{}", GREEN, RESET);
+    println!("\n{}Thank you for using Vonogs Scanner!{}", cyan(), reset());
+    println!("{}Goodbye!{}", green(), reset());
     thread::sleep(Duration::from_millis(1000));
     process::exit(0);
 }
 
 fn press_enter_with_message(message: &str) {
     let mut stdout = stdout();
-    write!(stdout, "\n{}{}{}", YELLOW, message, RESET).unwrap();
+    write!(stdout, "\n{}{}{}", yellow(), message, reset()).unwrap();
     stdout.flush().unwrap();
-    stdin().read(&mut [0]).unwrap();
+    let _ = stdin().read(&mut [0]).unwrap();
 
     clear_screen();
     print_menu_items();
@@ -909,3 +2582,59 @@ fn press_enter_with_message(message: &str) {
 fn press_enter_to_continue() {
     press_enter_with_message("Press Enter to continue...");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use findings::builtin_rules;
+
+    /// Reports are rebuilt from logs, so whatever the log writers produce
+    /// has to parse back into the same report.
+    #[test]
+    fn saved_logs_parse_back_into_the_report() {
+        let open_ports = vec![21, 443, 4444];
+        let mut findings = evaluate_rules(&builtin_rules(), &open_ports);
+        sort_findings(&mut findings);
+        let report = ScanReport {
+            scan_type: String::from("Port Range Scan"),
+            target: String::from("web01.example.net"),
+            host_name: String::new(),
+            start_time: String::new(),
+            end_time: String::from("2026-10-19_09-15-05"),
+            total_scanned: 1024,
+            elapsed_secs: 12.5,
+            open_ports,
+            details: vec![(443, String::from("TLSv1.3 | TLS_AES_128_GCM_SHA256"))],
+            findings,
+        };
+
+        let path = std::env::temp_dir().join(format!("vonogs-roundtrip-{}.log", process::id()));
+        let mut log_file = File::create(&path).unwrap();
+        let start_time = write_log_header(&mut log_file, &report.scan_type, &report.target, None);
+        write_log_entry(&mut log_file, "Port 20: CLOSED (connection refused)");
+        write_log_entry(&mut log_file, "Port 21: FTP - OPEN");
+        write_log_summary(&mut log_file, &report);
+        drop(log_file);
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let parsed = parse_log(&contents).unwrap();
+        assert_eq!(parsed.scan_type, report.scan_type);
+        assert_eq!(parsed.target, report.target);
+        assert_eq!(parsed.start_time, start_time);
+        assert_eq!(parsed.end_time, report.end_time);
+        assert_eq!(parsed.total_scanned, report.total_scanned);
+        assert_eq!(parsed.elapsed_secs, report.elapsed_secs);
+        assert_eq!(parsed.open_ports, report.open_ports);
+        assert_eq!(parsed.details, report.details);
+        assert_eq!(parsed.findings.len(), 2);
+        for (parsed, written) in parsed.findings.iter().zip(&report.findings) {
+            assert_eq!(parsed.rule_id, written.rule_id);
+            assert_eq!(parsed.port, written.port);
+            assert_eq!(parsed.severity, written.severity);
+            assert_eq!(parsed.title, written.title);
+            assert_eq!(parsed.description, written.description);
+            assert_eq!(parsed.remediation, written.remediation);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::findings::Finding;
+use crate::report::ScanReport;
+use crate::services::get_service_name;
+
+/// Escapes text from logs, which can hold banners and certificate names
+/// chosen by the scanned server, so it cannot add links, emphasis, HTML,
+/// table cells or new blocks.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (index, c) in text.chars().enumerate() {
+        match c {
+            '\n' | '\r' => escaped.push(' '),
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '~' | '!' | '&' | '#' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            // Would start a list item at the beginning of a line.
+            '-' | '+' if index == 0 => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A code span whose fence is longer than any backtick run inside `text`.
+fn code(text: &str) -> String {
+    let text = text.replace(['\n', '\r'], " ");
+    let longest_run = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run + 1);
+    if text.starts_with('`') || text.ends_with('`') {
+        format!("{} {} {}", fence, text, fence)
+    } else {
+        format!("{}{}{}", fence, text, fence)
+    }
+}
+
+fn render_finding(finding: &Finding) -> String {
+    let mut markdown = format!(
+        "- **[{}]** Port {}: {}",
+        finding.severity.label(),
+        finding.port,
+        escape(&finding.title)
+    );
+    if !finding.rule_id.is_empty() {
+        markdown.push_str(&format!(" ({})", code(&finding.rule_id)));
+    }
+    if !finding.description.is_empty() {
+        markdown.push_str(&format!("  \n  {}", escape(&finding.description)));
+    }
+    if !finding.remediation.is_empty() {
+        markdown.push_str(&format!(
+            "  \n  _Remediation:_ {}",
+            escape(&finding.remediation)
+        ));
+    }
+    markdown.push('\n');
+    markdown
+}
+
+fn render_scan(report: &ScanReport) -> String {
+    let mut markdown = format!(
+        "## {} — {}\n\n",
+        escape(&report.target),
+        escape(&report.scan_type)
+    );
+
+    markdown.push_str("| | |\n|---|---|\n");
+    let mut rows = vec![("Target", report.target.clone())];
+    if !report.host_name.is_empty() {
+        rows.push(("Reverse DNS", report.host_name.clone()));
+    }
+    rows.extend([
+        ("Scan Type", report.scan_type.clone()),
+        ("Start Time", report.start_time.clone()),
+        ("End Time", report.end_time.clone()),
+        ("Duration", format!("{:.2} seconds", report.elapsed_secs)),
+        ("Ports Scanned", report.total_scanned.to_string()),
+        ("Open Ports", report.open_ports.len().to_string()),
+        ("Findings", report.findings.len().to_string()),
+    ]);
+    for (label, value) in rows {
+        markdown.push_str(&format!("| **{}** | {} |\n", label, escape(&value)));
+    }
+
+    markdown.push_str("\n### Open Ports\n\n");
+    if report.open_ports.is_empty() {
+        markdown.push_str("No open ports found.\n");
+    } else {
+        markdown.push_str("| Port | Service | Details |\n|-----:|---------|---------|\n");
+        for port in &report.open_ports {
+            markdown.push_str(&format!(
+                "| {} | {} | {} |\n",
+                port,
+                escape(get_service_name(*port)),
+                escape(&report.details_for(*port).join("; "))
+            ));
+        }
+    }
+
+    markdown.push_str("\n### Findings\n\n");
+    if report.findings.is_empty() {
+        markdown.push_str("No findings.\n");
+    }
+    for finding in &report.findings {
+        markdown.push_str(&render_finding(finding));
+    }
+    markdown
+}
+
+/// Renders one or more scans as GitHub-flavoured Markdown for tickets and
+/// wiki pages.
+pub fn render_markdown(reports: &[ScanReport], generated_at: &str) -> String {
+    let mut markdown = format!(
+        "# Vonogs Scan Report\n\n_Generated {} · {} scan(s)_\n",
+        generated_at,
+        reports.len()
+    );
+    for report in reports {
+        markdown.push('\n');
+        markdown.push_str(&render_scan(report));
+    }
+    markdown
+}
@@ -0,0 +1,288 @@
+use crate::engine::{PortResult, PortStatus};
+use crate::services::get_service_name;
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// What the last watch cycle saw for one host.
+#[derive(Debug, Clone, Default)]
+pub struct HostMetrics {
+    pub up: bool,
+    pub ports_scanned: usize,
+    pub timeouts: usize,
+    pub duration_secs: f32,
+    pub last_scan: u64,
+    pub changes: u64,
+    /// Every port seen open so far; ports that closed again stay at `false`
+    /// so the series drops to 0 instead of disappearing.
+    pub ports: BTreeMap<u16, bool>,
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub cycles: u64,
+    pub hosts: BTreeMap<String, HostMetrics>,
+}
+
+pub type SharedMetrics = Arc<Mutex<Metrics>>;
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Metrics {
+    pub fn record_scan(&mut self, target: &str, results: &[PortResult], duration_secs: f32) {
+        let host = self.hosts.entry(target.to_string()).or_default();
+        host.up = true;
+        host.ports_scanned = results.len();
+        host.timeouts = results
+            .iter()
+            .filter(|r| r.status == PortStatus::TimeoutFiltered)
+            .count();
+        host.duration_secs = duration_secs;
+        host.last_scan = unix_time();
+        for open in host.ports.values_mut() {
+            *open = false;
+        }
+        for result in results.iter().filter(|r| r.status == PortStatus::Open) {
+            host.ports.insert(result.port, true);
+        }
+    }
+
+    /// Marks a host that could not be resolved or reached; its port series
+    /// keep their last known values.
+    pub fn record_down(&mut self, target: &str) {
+        let host = self.hosts.entry(target.to_string()).or_default();
+        host.up = false;
+        host.last_scan = unix_time();
+    }
+
+    pub fn record_changes(&mut self, target: &str, count: usize) {
+        self.hosts.entry(target.to_string()).or_default().changes += count as u64;
+    }
+}
+
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn family(text: &mut String, name: &str, kind: &str, help: &str) {
+    text.push_str(&format!(
+        "# HELP {} {}\n# TYPE {} {}\n",
+        name, help, name, kind
+    ));
+}
+
+fn per_host(
+    text: &mut String,
+    metrics: &Metrics,
+    name: &str,
+    value: impl Fn(&HostMetrics) -> String,
+) {
+    for (target, host) in &metrics.hosts {
+        text.push_str(&format!(
+            "{}{{host=\"{}\"}} {}\n",
+            name,
+            label(target),
+            value(host)
+        ));
+    }
+}
+
+/// Renders the current state in the Prometheus text exposition format.
+pub fn render(metrics: &Metrics) -> String {
+    let mut text = String::new();
+
+    family(
+        &mut text,
+        "vonogs_port_open",
+        "gauge",
+        "Whether a port was open in the last scan (1) or has closed since it was seen open (0).",
+    );
+    for (target, host) in &metrics.hosts {
+        for (port, open) in &host.ports {
+            text.push_str(&format!(
+                "vonogs_port_open{{host=\"{}\",port=\"{}\",service=\"{}\"}} {}\n",
+                label(target),
+                port,
+                label(get_service_name(*port)),
+                u8::from(*open)
+            ));
+        }
+    }
+
+    family(
+        &mut text,
+        "vonogs_open_ports",
+        "gauge",
+        "Number of open ports found in the last scan.",
+    );
+    per_host(&mut text, metrics, "vonogs_open_ports", |h| {
+        h.ports.values().filter(|open| **open).count().to_string()
+    });
+
+    family(
+        &mut text,
+        "vonogs_ports_scanned",
+        "gauge",
+        "Number of ports probed in the last scan.",
+    );
+    per_host(&mut text, metrics, "vonogs_ports_scanned", |h| {
+        h.ports_scanned.to_string()
+    });
+
+    family(
+        &mut text,
+        "vonogs_port_timeouts",
+        "gauge",
+        "Number of ports that timed out (filtered) in the last scan.",
+    );
+    per_host(&mut text, metrics, "vonogs_port_timeouts", |h| {
+        h.timeouts.to_string()
+    });
+
+    family(
+        &mut text,
+        "vonogs_scan_duration_seconds",
+        "gauge",
+        "Duration of the last scan of the host.",
+    );
+    per_host(&mut text, metrics, "vonogs_scan_duration_seconds", |h| {
+        format!("{:.3}", h.duration_secs)
+    });
+
+    family(
+        &mut text,
+        "vonogs_host_up",
+        "gauge",
+        "Whether the host resolved and was reachable in the last cycle.",
+    );
+    per_host(&mut text, metrics, "vonogs_host_up", |h| {
+        u8::from(h.up).to_string()
+    });
+
+    family(
+        &mut text,
+        "vonogs_last_scan_timestamp_seconds",
+        "gauge",
+        "Unix time of the last scan attempt.",
+    );
+    per_host(
+        &mut text,
+        metrics,
+        "vonogs_last_scan_timestamp_seconds",
+        |h| h.last_scan.to_string(),
+    );
+
+    family(
+        &mut text,
+        "vonogs_port_changes_total",
+        "counter",
+        "Port open, close and service changes seen since the watch started.",
+    );
+    per_host(&mut text, metrics, "vonogs_port_changes_total", |h| {
+        h.changes.to_string()
+    });
+
+    family(
+        &mut text,
+        "vonogs_watch_cycles_total",
+        "counter",
+        "Completed watch cycles.",
+    );
+    text.push_str(&format!("vonogs_watch_cycles_total {}\n", metrics.cycles));
+
+    text
+}
+
+/// Binds `listen` and serves `GET /metrics` on a background thread.
+pub fn serve(listen: &str, metrics: SharedMetrics) -> Result<(), String> {
+    let listener =
+        TcpListener::bind(listen).map_err(|e| format!("could not listen on {}: {}", listen, e))?;
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            let metrics = Arc::clone(&metrics);
+            thread::spawn(move || handle_connection(stream, metrics));
+        }
+    });
+    Ok(())
+}
+
+/// Returns the method and path of the request line; the scraper sends no body.
+fn read_request_line(stream: &mut TcpStream) -> Option<(String, String)> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !data.windows(4).any(|w| w == b"\r\n\r\n") {
+        if data.len() > MAX_REQUEST_BYTES {
+            return None;
+        }
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    let head = String::from_utf8_lossy(&data);
+    let mut request_line = head.lines().next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.split('?').next()?.to_string();
+    Some((method, path))
+}
+
+fn handle_connection(mut stream: TcpStream, metrics: SharedMetrics) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(10)));
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(10)));
+
+    let (status, content_type, body) = match read_request_line(&mut stream) {
+        Some((method, path)) if path == "/metrics" && method == "GET" => {
+            let body = match metrics.lock() {
+                Ok(metrics) => render(&metrics),
+                Err(poisoned) => render(&poisoned.into_inner()),
+            };
+            ("200 OK", "text/plain; version=0.0.4; charset=utf-8", body)
+        }
+        Some((_, path)) if path == "/metrics" => (
+            "405 Method Not Allowed",
+            "text/plain",
+            String::from("method not allowed\n"),
+        ),
+        Some(_) => (
+            "404 Not Found",
+            "text/plain",
+            String::from("not found; try /metrics\n"),
+        ),
+        None => (
+            "400 Bad Request",
+            "text/plain",
+            String::from("bad request\n"),
+        ),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\n\
+         Content-Type: {}\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
@@ -0,0 +1,366 @@
+use crate::engine::{PortResult, PortStatus};
+use crate::findings::{Finding, Severity};
+use crate::profiles::ScanProfile;
+use crate::services::get_service_name;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expectation {
+    MustBeOpen,
+    MayBeOpen,
+    MustBeClosed,
+}
+
+/// Expected exposure for one host, from a `[host <target>]` section.
+#[derive(Debug, Clone)]
+pub struct HostPolicy {
+    pub target: String,
+    pub allowed: Vec<u16>,
+    pub required: Vec<u16>,
+    pub extra_ports: Vec<u16>,
+    pub profile: ScanProfile,
+}
+
+impl HostPolicy {
+    fn new(target: &str) -> HostPolicy {
+        HostPolicy {
+            target: target.to_string(),
+            allowed: Vec::new(),
+            required: Vec::new(),
+            extra_ports: Vec::new(),
+            profile: ScanProfile::Full,
+        }
+    }
+
+    /// Profile ports plus every port the policy mentions, sorted and deduplicated.
+    pub fn ports_to_scan(&self) -> Vec<u16> {
+        let mut ports = self.profile.get_ports();
+        ports.extend(&self.allowed);
+        ports.extend(&self.required);
+        ports.extend(&self.extra_ports);
+        ports.sort_unstable();
+        ports.dedup();
+        ports
+    }
+
+    pub fn expectation(&self, port: u16) -> Expectation {
+        if self.required.contains(&port) {
+            Expectation::MustBeOpen
+        } else if self.allowed.contains(&port) {
+            Expectation::MayBeOpen
+        } else {
+            Expectation::MustBeClosed
+        }
+    }
+}
+
+/// The outcome of comparing one scanned port against its host policy.
+#[derive(Debug, Clone)]
+pub struct PolicyCheck {
+    pub target: String,
+    pub port: u16,
+    pub expectation: Expectation,
+    pub status: PortStatus,
+    pub passed: bool,
+}
+
+impl PolicyCheck {
+    pub fn message(&self) -> String {
+        let service = get_service_name(self.port);
+        let status = self.status.label();
+        match (self.expectation, self.passed) {
+            (Expectation::MustBeOpen, true) => {
+                format!("port {} {} {} as required", self.port, service, status)
+            }
+            (Expectation::MustBeOpen, false) => {
+                format!("port {} {} required but {}", self.port, service, status)
+            }
+            (Expectation::MayBeOpen, _) => {
+                format!("port {} {} {} (allowed)", self.port, service, status)
+            }
+            (Expectation::MustBeClosed, true) => {
+                format!("port {} {} {} as expected", self.port, service, status)
+            }
+            (Expectation::MustBeClosed, false) => {
+                format!("port {} {} unexpectedly {}", self.port, service, status)
+            }
+        }
+    }
+
+    /// A failed check expressed as a finding, for SARIF and other exports.
+    pub fn finding(&self) -> Option<Finding> {
+        if self.passed {
+            return None;
+        }
+        let service = get_service_name(self.port);
+        let (rule_id, severity, title, remediation) = match self.expectation {
+            Expectation::MustBeOpen => (
+                "policy-required-port-closed",
+                Severity::Medium,
+                "Required port is not open",
+                format!(
+                    "Check that {} is running and reachable on port {}, or drop it from `require`.",
+                    service, self.port
+                ),
+            ),
+            _ => (
+                "policy-unexpected-open-port",
+                Severity::High,
+                "Port open but not allowed by policy",
+                format!(
+                    "Close port {} or add it to `allow` in the policy if the exposure is intended.",
+                    self.port
+                ),
+            ),
+        };
+        Some(Finding {
+            rule_id: String::from(rule_id),
+            port: self.port,
+            severity,
+            title: String::from(title),
+            description: format!("{} on {}", self.message(), self.target),
+            remediation,
+        })
+    }
+}
+
+pub fn evaluate(policy: &HostPolicy, results: &[PortResult]) -> Vec<PolicyCheck> {
+    results
+        .iter()
+        .map(|result| {
+            let expectation = policy.expectation(result.port);
+            let is_open = result.status == PortStatus::Open;
+            let passed = match expectation {
+                Expectation::MustBeOpen => is_open,
+                Expectation::MayBeOpen => true,
+                Expectation::MustBeClosed => !is_open,
+            };
+            PolicyCheck {
+                target: policy.target.clone(),
+                port: result.port,
+                expectation,
+                status: result.status,
+                passed,
+            }
+        })
+        .collect()
+}
+
+/// The exit code of `vonogs policy`: 2 when a target could not be checked,
+/// since a violation may hide behind it, 1 on violations, 0 otherwise.
+pub fn exit_code(unusable_targets: usize, violations: usize) -> i32 {
+    if unusable_targets > 0 {
+        2
+    } else if violations > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Parses a comma-separated list of ports and `start-end` ranges.
+pub fn parse_port_list(value: &str) -> Result<Vec<u16>, String> {
+    let mut ports = Vec::new();
+    for part in value.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let invalid = || format!("invalid port '{}'", part);
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start = start.trim().parse::<u16>().map_err(|_| invalid())?;
+                let end = end.trim().parse::<u16>().map_err(|_| invalid())?;
+                if start == 0 || start > end {
+                    return Err(invalid());
+                }
+                ports.extend(start..=end);
+            }
+            None => match part.parse::<u16>() {
+                Ok(port) if port != 0 => ports.push(port),
+                _ => return Err(invalid()),
+            },
+        }
+    }
+    Ok(ports)
+}
+
+pub fn parse_ports(value: &str, line_number: usize) -> Result<Vec<u16>, String> {
+    parse_port_list(value).map_err(|e| format!("line {}: {}", line_number, e))
+}
+
+/// Parses `[host <target>]` sections with `allow`, `require`, `ports` and
+/// `profile` keys. Any error makes the whole policy invalid.
+pub fn parse_policy(contents: &str) -> Result<Vec<HostPolicy>, String> {
+    let mut hosts: Vec<HostPolicy> = Vec::new();
+
+    for (index, raw_line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            match header.trim().strip_prefix("host ") {
+                Some(target) if !target.trim().is_empty() => {
+                    hosts.push(HostPolicy::new(target.trim()))
+                }
+                _ => return Err(format!("line {}: expected [host <target>]", line_number)),
+            }
+            continue;
+        }
+
+        let host = hosts
+            .last_mut()
+            .ok_or_else(|| format!("line {}: setting outside a host section", line_number))?;
+        let (key, value) = line
+            .split_once('=')
+            .map(|(k, v)| (k.trim(), v.trim()))
+            .ok_or_else(|| format!("line {}: expected key = value", line_number))?;
+
+        match key {
+            "allow" => host.allowed.extend(parse_ports(value, line_number)?),
+            "require" => host.required.extend(parse_ports(value, line_number)?),
+            "ports" => host.extra_ports.extend(parse_ports(value, line_number)?),
+            "profile" => {
+                host.profile = ScanProfile::from_name(value)
+                    .ok_or_else(|| format!("line {}: unknown profile '{}'", line_number, value))?
+            }
+            other => return Err(format!("line {}: unknown key '{}'", line_number, other)),
+        }
+    }
+
+    if hosts.is_empty() {
+        return Err(String::from("no [host <target>] sections found"));
+    }
+    Ok(hosts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Reason;
+
+    fn result(port: u16, status: PortStatus) -> PortResult {
+        PortResult {
+            port,
+            status,
+            reason: Reason::Connected,
+        }
+    }
+
+    #[test]
+    fn parses_host_sections() {
+        let hosts = parse_policy(
+            "# production web tier\n\
+             [host web.example.com]\n\
+             allow = 80, 443\n\
+             require = 443  # must stay up\n\
+             ports = 8000-8002\n\
+             profile = web\n\
+             \n\
+             [host 10.0.0.5]\n\
+             allow = 22\n",
+        )
+        .unwrap();
+        assert_eq!(hosts.len(), 2);
+        assert_eq!(hosts[0].target, "web.example.com");
+        assert_eq!(hosts[0].allowed, vec![80, 443]);
+        assert_eq!(hosts[0].required, vec![443]);
+        assert_eq!(hosts[0].extra_ports, vec![8000, 8001, 8002]);
+        assert!(matches!(hosts[0].profile, ScanProfile::Web));
+        assert_eq!(hosts[1].target, "10.0.0.5");
+        assert!(matches!(hosts[1].profile, ScanProfile::Full));
+
+        let ports = hosts[0].ports_to_scan();
+        assert!(ports.windows(2).all(|w| w[0] < w[1]));
+        assert!(ports.contains(&8001) && ports.contains(&8443));
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let error = |contents: &str| parse_policy(contents).unwrap_err();
+        assert_eq!(error(""), "no [host <target>] sections found");
+        assert_eq!(
+            error("# only comments\n"),
+            "no [host <target>] sections found"
+        );
+        assert_eq!(
+            error("allow = 22"),
+            "line 1: setting outside a host section"
+        );
+        assert_eq!(error("[host ]"), "line 1: expected [host <target>]");
+        assert_eq!(error("[server a]"), "line 1: expected [host <target>]");
+        assert_eq!(error("[host a]\nallow 22"), "line 2: expected key = value");
+        assert_eq!(error("[host a]\ndeny = 22"), "line 2: unknown key 'deny'");
+        assert_eq!(
+            error("[host a]\nprofile = huge"),
+            "line 2: unknown profile 'huge'"
+        );
+        assert_eq!(
+            error("[host a]\n\nrequire = 22,http"),
+            "line 3: invalid port 'http'"
+        );
+    }
+
+    #[test]
+    fn parses_port_lists_and_ranges() {
+        assert_eq!(parse_port_list("22, 80,,443").unwrap(), vec![22, 80, 443]);
+        assert_eq!(parse_port_list("1-3,5").unwrap(), vec![1, 2, 3, 5]);
+        assert_eq!(parse_port_list("7 - 7").unwrap(), vec![7]);
+        assert_eq!(parse_port_list("65534-65535").unwrap(), vec![65534, 65535]);
+        assert_eq!(parse_port_list("").unwrap(), Vec::<u16>::new());
+
+        for bad in [
+            "0", "65536", "0-10", "10-5", "1-65536", "-5", "5-", "1-2-3", "x",
+        ] {
+            assert_eq!(
+                parse_port_list(bad),
+                Err(format!("invalid port '{}'", bad)),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn evaluates_ports_against_the_policy() {
+        let hosts = parse_policy("[host a]\nallow = 80\nrequire = 443").unwrap();
+        let checks = evaluate(
+            &hosts[0],
+            &[
+                result(22, PortStatus::Open),
+                result(23, PortStatus::TimeoutFiltered),
+                result(80, PortStatus::Closed),
+                result(443, PortStatus::Closed),
+            ],
+        );
+        let verdicts: Vec<(u16, Expectation, bool)> = checks
+            .iter()
+            .map(|c| (c.port, c.expectation, c.passed))
+            .collect();
+        assert_eq!(
+            verdicts,
+            vec![
+                (22, Expectation::MustBeClosed, false),
+                (23, Expectation::MustBeClosed, true),
+                (80, Expectation::MayBeOpen, true),
+                (443, Expectation::MustBeOpen, false),
+            ]
+        );
+        assert_eq!(
+            checks[0].finding().unwrap().rule_id,
+            "policy-unexpected-open-port"
+        );
+        assert_eq!(
+            checks[3].finding().unwrap().rule_id,
+            "policy-required-port-closed"
+        );
+        assert!(checks[1].finding().is_none());
+    }
+
+    #[test]
+    fn picks_the_exit_code() {
+        assert_eq!(exit_code(0, 0), 0);
+        assert_eq!(exit_code(0, 3), 1);
+        assert_eq!(exit_code(1, 0), 2);
+        // An unchecked host outweighs violations found elsewhere.
+        assert_eq!(exit_code(1, 3), 2);
+    }
+}
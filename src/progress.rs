@@ -0,0 +1,343 @@
+use crate::color::{self, fit, terminal_width, visible_width, GREEN, MAGENTA, RED, RESET, YELLOW};
+use crate::engine::PortStatus;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Redraws of the in-place line are throttled to this interval.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+/// How often a plain progress line is printed when stdout is not a terminal.
+const PLAIN_INTERVAL: Duration = Duration::from_secs(5);
+/// The smoothed rate takes a new sample once this much time has passed.
+const RATE_WINDOW: Duration = Duration::from_millis(500);
+/// Weight of the newest sample in the smoothed rate.
+const SMOOTHING: f64 = 0.3;
+
+/// Formats seconds as `m:ss`, or `h:mm:ss` from an hour up.
+pub fn clock(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+struct State {
+    total: usize,
+    open: usize,
+    closed: usize,
+    filtered: usize,
+    unreachable: usize,
+    errors: usize,
+    started: Instant,
+    stopped: Option<Instant>,
+    paused_since: Option<Instant>,
+    paused_total: Duration,
+    window_started: Instant,
+    window_done: usize,
+    rate: Option<f64>,
+    last_drawn: Option<Instant>,
+    width: Option<usize>,
+}
+
+impl State {
+    fn done(&self) -> usize {
+        self.open + self.closed + self.filtered + self.unreachable + self.errors
+    }
+
+    fn elapsed(&self) -> Duration {
+        let end = self.stopped.unwrap_or_else(Instant::now);
+        let paused = self.paused_total + self.paused_since.map_or(Duration::ZERO, |p| end - p);
+        end.duration_since(self.started).saturating_sub(paused)
+    }
+}
+
+/// Counts and timing shared by every scan mode. Safe to update from several
+/// worker threads; covers all hosts of a scan, not just the current one.
+#[derive(Debug, Clone, Copy)]
+pub struct Snapshot {
+    pub done: usize,
+    pub total: usize,
+    pub open: usize,
+    pub closed: usize,
+    pub filtered: usize,
+    /// Host or network unreachable.
+    pub unreachable: usize,
+    /// Permission denied, out of resources or an invalid address.
+    pub errors: usize,
+    pub elapsed: Duration,
+    /// Smoothed ports per second.
+    pub rate: f64,
+    pub eta: Option<Duration>,
+}
+
+impl Snapshot {
+    pub fn percent(&self) -> usize {
+        (self.done * 100).checked_div(self.total).unwrap_or(100)
+    }
+
+    /// One line of plain text, for logs and non-terminal output.
+    pub fn plain_line(&self) -> String {
+        format!(
+            "Progress: {}/{} ports ({}%), {:.1} ports/s, elapsed {}, ETA {}, open {}, closed {}, filtered {}{}{}",
+            self.done,
+            self.total,
+            self.percent(),
+            self.rate,
+            clock(self.elapsed),
+            self.eta.map_or(String::from("unknown"), clock),
+            self.open,
+            self.closed,
+            self.filtered,
+            if self.unreachable > 0 {
+                format!(", unreachable {}", self.unreachable)
+            } else {
+                String::new()
+            },
+            if self.errors > 0 {
+                format!(", errors {}", self.errors)
+            } else {
+                String::new()
+            }
+        )
+    }
+}
+
+pub struct Progress {
+    state: Mutex<State>,
+    tty: bool,
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Progress {
+        let now = Instant::now();
+        Progress {
+            state: Mutex::new(State {
+                total,
+                open: 0,
+                closed: 0,
+                filtered: 0,
+                unreachable: 0,
+                errors: 0,
+                started: now,
+                stopped: None,
+                paused_since: None,
+                paused_total: Duration::ZERO,
+                window_started: now,
+                window_done: 0,
+                rate: None,
+                last_drawn: None,
+                width: None,
+            }),
+            tty: color::redraw(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn record(&self, status: PortStatus) {
+        let mut state = self.lock();
+        match status {
+            PortStatus::Open => state.open += 1,
+            PortStatus::Closed => state.closed += 1,
+            PortStatus::TimeoutFiltered => state.filtered += 1,
+            PortStatus::HostUnreachable | PortStatus::NetworkUnreachable => state.unreachable += 1,
+            PortStatus::PermissionDenied
+            | PortStatus::ResourceExhausted
+            | PortStatus::InvalidAddress
+            | PortStatus::ProxyError => state.errors += 1,
+        }
+
+        let window = state.window_started.elapsed();
+        if window >= RATE_WINDOW && state.paused_since.is_none() {
+            let sample = (state.done() - state.window_done) as f64 / window.as_secs_f64();
+            state.rate = Some(match state.rate {
+                Some(rate) => rate * (1.0 - SMOOTHING) + sample * SMOOTHING,
+                None => sample,
+            });
+            state.window_started = Instant::now();
+            state.window_done = state.done();
+        }
+    }
+
+    /// Takes ports that will never be probed, e.g. of an unresolved host,
+    /// out of the total.
+    pub fn skip(&self, count: usize) {
+        let mut state = self.lock();
+        state.total = state.total.saturating_sub(count);
+    }
+
+    pub fn pause(&self) {
+        let mut state = self.lock();
+        state.paused_since.get_or_insert_with(Instant::now);
+    }
+
+    pub fn resume(&self) {
+        let mut state = self.lock();
+        if let Some(since) = state.paused_since.take() {
+            state.paused_total += since.elapsed();
+            // Start a fresh sample so the pause does not drag the rate down.
+            state.window_started = Instant::now();
+            state.window_done = state.done();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.lock().paused_since.is_some()
+    }
+
+    /// Freezes the elapsed time once the scan has ended.
+    pub fn stop(&self) {
+        self.resume();
+        let mut state = self.lock();
+        state.stopped.get_or_insert_with(Instant::now);
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let state = self.lock();
+        let done = state.done();
+        let elapsed = state.elapsed();
+        let average = if elapsed.as_secs_f64() > 0.0 {
+            done as f64 / elapsed.as_secs_f64()
+        } else {
+            0.0
+        };
+        let rate = state.rate.unwrap_or(average);
+        let remaining = state.total.saturating_sub(done);
+        let eta = if remaining == 0 {
+            Some(Duration::ZERO)
+        } else if rate > 0.0 {
+            Some(Duration::from_secs_f64(remaining as f64 / rate))
+        } else {
+            None
+        };
+        Snapshot {
+            done,
+            total: state.total,
+            open: state.open,
+            closed: state.closed,
+            filtered: state.filtered,
+            unreachable: state.unreachable,
+            errors: state.errors,
+            elapsed,
+            rate,
+            eta,
+        }
+    }
+
+    /// The numbers always fit; the bar shrinks, and goes, on narrow terminals.
+    fn terminal_line(&self, label: &str, snapshot: &Snapshot, width: usize) -> String {
+        let mut counts = format!(
+            "{}% {}/{} | {:.1} ports/s | {} | ETA {} | {}open {}{} closed {} {}filtered {}{}",
+            snapshot.percent(),
+            snapshot.done,
+            snapshot.total,
+            snapshot.rate,
+            clock(snapshot.elapsed),
+            snapshot.eta.map_or(String::from("--:--"), clock),
+            GREEN,
+            snapshot.open,
+            RESET,
+            snapshot.closed,
+            YELLOW,
+            snapshot.filtered,
+            RESET
+        );
+        if snapshot.unreachable > 0 {
+            counts.push_str(&format!(
+                " {}unreachable {}{}",
+                MAGENTA, snapshot.unreachable, RESET
+            ));
+        }
+        if snapshot.errors > 0 {
+            counts.push_str(&format!(" {}errors {}{}", RED, snapshot.errors, RESET));
+        }
+        let label = if label.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", label)
+        };
+
+        let room = width.saturating_sub(visible_width(&label) + visible_width(&counts) + 3);
+        let bar_width = room.min(20);
+        if bar_width < 5 {
+            return format!("{}{}", label, counts);
+        }
+        let filled = (bar_width * snapshot.done)
+            .checked_div(snapshot.total)
+            .unwrap_or(bar_width)
+            .min(bar_width);
+        let mut bar = format!("{}{}{}", GREEN, "=".repeat(filled), RESET);
+        if filled < bar_width {
+            bar.push_str(&format!(
+                "{}>{}{}",
+                YELLOW,
+                RESET,
+                " ".repeat(bar_width - filled - 1)
+            ));
+        }
+        format!("{}[{}] {}", label, bar, counts)
+    }
+
+    /// Redraws the progress line in place on a terminal, or prints a plain
+    /// line every few seconds otherwise. `label` names the current work.
+    pub fn tick(&self, label: &str) {
+        let interval = if self.tty {
+            REDRAW_INTERVAL
+        } else {
+            PLAIN_INTERVAL
+        };
+        {
+            let mut state = self.lock();
+            let now = Instant::now();
+            match state.last_drawn {
+                Some(last) if now - last < interval => return,
+                // Plain output waits one interval before its first line.
+                None if !self.tty => {
+                    state.last_drawn = Some(now);
+                    return;
+                }
+                _ => state.last_drawn = Some(now),
+            }
+            if self.tty && state.width.is_none() {
+                state.width = Some(terminal_width().unwrap_or(80));
+            }
+        }
+
+        let snapshot = self.snapshot();
+        let mut stdout = io::stdout();
+        if self.tty {
+            let width = self.lock().width.unwrap_or(80).saturating_sub(1);
+            let line = fit(&self.terminal_line(label, &snapshot, width), width);
+            let _ = write!(stdout, "\r\x1b[2K{}{}", line, RESET);
+        } else {
+            let _ = writeln!(stdout, "{}", snapshot.plain_line());
+        }
+        let _ = stdout.flush();
+    }
+
+    /// Removes the progress line so other output can be printed; the next
+    /// `tick` draws it again straight away.
+    pub fn clear(&self) {
+        if self.tty {
+            print!("\r\x1b[2K");
+            let _ = io::stdout().flush();
+            self.lock().last_drawn = None;
+        }
+    }
+
+    /// Stops the clock and clears the line, or prints a final plain line.
+    pub fn finish(&self) {
+        self.stop();
+        if self.tty {
+            self.clear();
+        } else if self.lock().last_drawn.is_some() {
+            println!("{}", self.snapshot().plain_line());
+        }
+    }
+}
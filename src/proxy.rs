@@ -0,0 +1,521 @@
+use crate::engine::Reason;
+use crate::resolve::resolve;
+use crate::trace;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Longest CONNECT response header read before giving up on the proxy.
+const MAX_RESPONSE_HEADER: usize = 16 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scheme {
+    Socks5,
+    /// An HTTP proxy's `CONNECT` method.
+    Http,
+}
+
+impl Scheme {
+    fn from_name(name: &str) -> Option<Scheme> {
+        match name.to_lowercase().as_str() {
+            "socks5" => Some(Scheme::Socks5),
+            "http" => Some(Scheme::Http),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Scheme::Socks5 => "socks5",
+            Scheme::Http => "http",
+        }
+    }
+
+    fn default_port(&self) -> u16 {
+        match self {
+            Scheme::Socks5 => 1080,
+            Scheme::Http => 3128,
+        }
+    }
+}
+
+/// The `--proxy` every probe and inspection connects through.
+#[derive(Debug, Clone)]
+pub struct Proxy {
+    pub scheme: Scheme,
+    pub addr: SocketAddr,
+    credentials: Option<(String, String)>,
+}
+
+static PROXY: OnceLock<Proxy> = OnceLock::new();
+
+pub fn set(proxy: Proxy) {
+    let _ = PROXY.set(proxy);
+}
+
+/// `None` connects directly.
+pub fn current() -> Option<&'static Proxy> {
+    PROXY.get()
+}
+
+/// Why a connection through the proxy was not made.
+pub enum Failure {
+    /// The proxy refused the CONNECT with this SOCKS5 reply code.
+    Socks(u8),
+    /// The HTTP proxy answered the CONNECT with this non-2xx status.
+    Http(u16),
+    /// No reply to the CONNECT within the timeout.
+    TimedOut,
+    /// The proxy hung up instead of replying, which is how OpenSSH's
+    /// dynamic forwarding reports a failed connect.
+    Closed,
+    /// The proxy itself could not be used.
+    Proxy(&'static str),
+}
+
+impl Failure {
+    pub fn reason(&self) -> Reason {
+        match self {
+            Failure::Socks(code) => Reason::Socks(*code),
+            Failure::Http(status) => Reason::Http(*status),
+            Failure::TimedOut => Reason::TimedOut,
+            Failure::Closed => Reason::ProxyClosed,
+            Failure::Proxy(message) => Reason::Proxy(message),
+        }
+    }
+}
+
+/// Decodes `%xx` escapes, so credentials may contain `:` or `@`.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = value
+            .get(i + 1..i + 3)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Standard base64 with padding, for `Proxy-Authorization: Basic`.
+fn base64(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// `host`, `host:port`, `[v6]` or `[v6]:port`.
+fn split_host_port(value: &str, default_port: u16) -> Result<(String, u16), String> {
+    let (host, port) = if let Some(rest) = value.strip_prefix('[') {
+        let (host, after) = rest
+            .split_once(']')
+            .ok_or_else(|| format!("--proxy: missing ']' in '{}'", value))?;
+        (host, after.strip_prefix(':'))
+    } else {
+        match value.split_once(':') {
+            Some((host, port)) if !port.contains(':') => (host, Some(port)),
+            _ => (value, None),
+        }
+    };
+    if host.is_empty() {
+        return Err(String::from("--proxy: missing proxy host"));
+    }
+    let port = match port {
+        Some(port) => port
+            .parse::<u16>()
+            .ok()
+            .filter(|port| *port != 0)
+            .ok_or_else(|| format!("--proxy: invalid port '{}'", port))?,
+        None => default_port,
+    };
+    Ok((host.to_string(), port))
+}
+
+impl Proxy {
+    /// Parses `socks5://` or `http://[user[:password]@]host[:port]` and
+    /// resolves the proxy host once. Without credentials in the URL,
+    /// `VONOGS_PROXY_USER` and `VONOGS_PROXY_PASSWORD` are used, which keeps
+    /// the password out of the process list.
+    pub fn parse(url: &str) -> Result<Proxy, String> {
+        let (scheme, rest) = url.split_once("://").ok_or_else(|| {
+            format!(
+                "--proxy: '{}' needs a scheme, e.g. socks5://127.0.0.1:1080",
+                url
+            )
+        })?;
+        let scheme = Scheme::from_name(scheme)
+            .ok_or_else(|| format!("--proxy: unsupported scheme '{}'", scheme))?;
+        let rest = rest.trim_end_matches('/');
+        let (userinfo, host_port) = match rest.rsplit_once('@') {
+            Some((userinfo, host_port)) => (Some(userinfo), host_port),
+            None => (None, rest),
+        };
+
+        let credentials = match userinfo {
+            Some(userinfo) => {
+                let (user, password) = userinfo.split_once(':').unwrap_or((userinfo, ""));
+                Some((percent_decode(user), percent_decode(password)))
+            }
+            None => std::env::var("VONOGS_PROXY_USER").ok().map(|user| {
+                let password = std::env::var("VONOGS_PROXY_PASSWORD").unwrap_or_default();
+                (user, password)
+            }),
+        };
+        if let Some((user, password)) = &credentials {
+            if user.is_empty() || user.len() > 255 || password.len() > 255 {
+                return Err(String::from(
+                    "--proxy: username must be 1 to 255 bytes and password at most 255",
+                ));
+            }
+        }
+
+        let (host, port) = split_host_port(host_port, scheme.default_port())?;
+        let addr = resolve(&host)
+            .map_err(|e| format!("--proxy: {}", e))?
+            .socket_addr(port);
+        Ok(Proxy {
+            scheme,
+            addr,
+            credentials,
+        })
+    }
+
+    /// `socks5://127.0.0.1:1080`, without credentials, for logs and traces.
+    pub fn label(&self) -> String {
+        format!("{}://{}", self.scheme.name(), self.addr)
+    }
+
+    /// Opens a connection to `target` through the proxy. When `host` is a
+    /// name rather than an address, the proxy is asked for the name so it
+    /// resolves it on its side. Read and write timeouts are cleared again
+    /// once the tunnel stands.
+    pub fn open(
+        &self,
+        target: SocketAddr,
+        host: &str,
+        timeout: Duration,
+    ) -> Result<TcpStream, Failure> {
+        let mut stream = TcpStream::connect_timeout(&self.addr, timeout).map_err(|e| {
+            self.trace_error("connect to", &e);
+            Failure::Proxy("could not connect to the proxy")
+        })?;
+        let _ = stream.set_read_timeout(Some(timeout));
+        let _ = stream.set_write_timeout(Some(timeout));
+        match self.scheme {
+            Scheme::Socks5 => self.socks5_connect(&mut stream, target, host_name(host))?,
+            Scheme::Http => self.http_connect(&mut stream, target, host_name(host))?,
+        }
+        let _ = stream.set_read_timeout(None);
+        let _ = stream.set_write_timeout(None);
+        Ok(stream)
+    }
+
+    fn trace_error(&self, stage: &str, error: &io::Error) {
+        trace::log(2, &format!("[vv] {} {}: {}", stage, self.label(), error));
+    }
+
+    fn handshake_failed(&self, error: io::Error) -> Failure {
+        self.trace_error("handshake with", &error);
+        Failure::Proxy("proxy handshake failed")
+    }
+
+    /// Sends `CONNECT` and reads the response headers one byte at a time,
+    /// so nothing the target sends first is swallowed.
+    fn http_connect(
+        &self,
+        stream: &mut TcpStream,
+        target: SocketAddr,
+        name: Option<&str>,
+    ) -> Result<(), Failure> {
+        let authority = match name {
+            Some(name) => format!("{}:{}", name, target.port()),
+            None => target.to_string(),
+        };
+        let mut request = format!("CONNECT {} HTTP/1.1\r\nHost: {}\r\n", authority, authority);
+        if let Some((user, password)) = &self.credentials {
+            request.push_str(&format!(
+                "Proxy-Authorization: Basic {}\r\n",
+                base64(format!("{}:{}", user, password).as_bytes())
+            ));
+        }
+        request.push_str("User-Agent: vonogs\r\n\r\n");
+        stream
+            .write_all(request.as_bytes())
+            .map_err(|e| self.handshake_failed(e))?;
+
+        let mut response = Vec::new();
+        let mut byte = [0u8; 1];
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= MAX_RESPONSE_HEADER {
+                return Err(Failure::Proxy("HTTP proxy response headers too long"));
+            }
+            match stream.read(&mut byte) {
+                Ok(0) => return Err(Failure::Closed),
+                Ok(_) => response.push(byte[0]),
+                Err(e) => {
+                    return Err(match e.kind() {
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => Failure::TimedOut,
+                        io::ErrorKind::ConnectionReset => Failure::Closed,
+                        _ => self.handshake_failed(e),
+                    })
+                }
+            }
+        }
+
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or_default();
+        trace::log(
+            2,
+            &format!("[vv] {} answered {}", self.label(), status_line),
+        );
+        let status = status_line
+            .strip_prefix("HTTP/1.")
+            .and_then(|rest| rest.get(2..5))
+            .and_then(|code| code.parse::<u16>().ok())
+            .ok_or(Failure::Proxy("malformed HTTP proxy response"))?;
+        if (200..300).contains(&status) {
+            Ok(())
+        } else {
+            Err(Failure::Http(status))
+        }
+    }
+
+    fn socks5_connect(
+        &self,
+        stream: &mut TcpStream,
+        target: SocketAddr,
+        name: Option<&str>,
+    ) -> Result<(), Failure> {
+        // Version 5, offering no authentication and, with credentials,
+        // username/password (RFC 1929).
+        let greeting: &[u8] = if self.credentials.is_some() {
+            &[5, 2, 0x00, 0x02]
+        } else {
+            &[5, 1, 0x00]
+        };
+        stream
+            .write_all(greeting)
+            .map_err(|e| self.handshake_failed(e))?;
+        let mut choice = [0u8; 2];
+        stream
+            .read_exact(&mut choice)
+            .map_err(|e| self.handshake_failed(e))?;
+        if choice[0] != 5 {
+            return Err(Failure::Proxy("not a SOCKS5 proxy"));
+        }
+        match choice[1] {
+            0x00 => {}
+            0x02 if self.credentials.is_some() => self.socks5_login(stream)?,
+            _ => {
+                return Err(Failure::Proxy(
+                    "proxy accepted none of the offered authentication methods",
+                ))
+            }
+        }
+
+        let mut request = vec![5, 1, 0];
+        match (name, target.ip()) {
+            (Some(name), _) => {
+                request.push(0x03);
+                request.push(name.len() as u8);
+                request.extend_from_slice(name.as_bytes());
+            }
+            (None, IpAddr::V4(ip)) => {
+                request.push(0x01);
+                request.extend_from_slice(&ip.octets());
+            }
+            (None, IpAddr::V6(ip)) => {
+                request.push(0x04);
+                request.extend_from_slice(&ip.octets());
+            }
+        }
+        request.extend_from_slice(&target.port().to_be_bytes());
+        stream
+            .write_all(&request)
+            .map_err(|e| self.handshake_failed(e))?;
+
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).map_err(|e| match e.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => Failure::TimedOut,
+            io::ErrorKind::UnexpectedEof | io::ErrorKind::ConnectionReset => Failure::Closed,
+            _ => self.handshake_failed(e),
+        })?;
+        if reply[0] != 5 {
+            return Err(Failure::Proxy("malformed SOCKS5 reply"));
+        }
+        if reply[1] != 0x00 {
+            return Err(Failure::Socks(reply[1]));
+        }
+
+        // The address the proxy connected from; not needed, but it has to
+        // be read before the tunnel carries data.
+        let bound_len = match reply[3] {
+            0x01 => 4,
+            0x04 => 16,
+            0x03 => {
+                let mut len = [0u8; 1];
+                stream
+                    .read_exact(&mut len)
+                    .map_err(|e| self.handshake_failed(e))?;
+                usize::from(len[0])
+            }
+            _ => return Err(Failure::Proxy("malformed SOCKS5 reply")),
+        };
+        let mut bound = vec![0u8; bound_len + 2];
+        stream
+            .read_exact(&mut bound)
+            .map_err(|e| self.handshake_failed(e))?;
+        Ok(())
+    }
+
+    fn socks5_login(&self, stream: &mut TcpStream) -> Result<(), Failure> {
+        let Some((user, password)) = &self.credentials else {
+            return Err(Failure::Proxy("proxy asked for a username and password"));
+        };
+        let mut request = vec![1, user.len() as u8];
+        request.extend_from_slice(user.as_bytes());
+        request.push(password.len() as u8);
+        request.extend_from_slice(password.as_bytes());
+        stream
+            .write_all(&request)
+            .map_err(|e| self.handshake_failed(e))?;
+        let mut status = [0u8; 2];
+        stream
+            .read_exact(&mut status)
+            .map_err(|e| self.handshake_failed(e))?;
+        if status[1] != 0x00 {
+            return Err(Failure::Proxy("proxy rejected the username or password"));
+        }
+        Ok(())
+    }
+}
+
+/// The name to ask the proxy for, or `None` when `host` is an address,
+/// empty or too long for a SOCKS5 request, which then gets `target`.
+fn host_name(host: &str) -> Option<&str> {
+    let host = host.trim();
+    let is_address = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .is_ok();
+    Some(host).filter(|host| !host.is_empty() && host.len() <= 255 && !is_address)
+}
+
+/// Connects to `addr` directly, or through the `--proxy` when one is set;
+/// what inspections use so they reach the same ports the scan did. `host`
+/// is the target as given, so the proxy can be asked for it by name.
+pub fn connect(addr: SocketAddr, host: &str, timeout: Duration) -> io::Result<TcpStream> {
+    match current() {
+        Some(proxy) => proxy.open(addr, host, timeout).map_err(|failure| {
+            io::Error::other(format!(
+                "via {}: {}",
+                proxy.label(),
+                failure.reason().label()
+            ))
+        }),
+        None => TcpStream::connect_timeout(&addr, timeout),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// A SOCKS5 proxy without authentication that accepts one CONNECT and
+    /// returns the request it was sent.
+    fn socks_stub() -> (Proxy, thread::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy = Proxy {
+            scheme: Scheme::Socks5,
+            addr: listener.local_addr().unwrap(),
+            credentials: None,
+        };
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).unwrap();
+            stream.write_all(&[5, 0x00]).unwrap();
+            let mut request = vec![0u8; 4];
+            stream.read_exact(&mut request).unwrap();
+            let address_len = match request[3] {
+                0x01 => 4,
+                0x04 => 16,
+                _ => {
+                    let mut len = [0u8; 1];
+                    stream.read_exact(&mut len).unwrap();
+                    request.push(len[0]);
+                    usize::from(len[0])
+                }
+            };
+            let mut rest = vec![0u8; address_len + 2];
+            stream.read_exact(&mut rest).unwrap();
+            request.extend_from_slice(&rest);
+            stream
+                .write_all(&[5, 0x00, 0, 1, 0, 0, 0, 0, 0, 0])
+                .unwrap();
+            request
+        });
+        (proxy, handle)
+    }
+
+    #[test]
+    fn sends_hostnames_by_name() {
+        let (proxy, handle) = socks_stub();
+        let target = SocketAddr::from(([192, 0, 2, 10], 443));
+        assert!(proxy
+            .open(target, "db.internal", Duration::from_secs(2))
+            .is_ok());
+        let mut expected = vec![5, 1, 0, 0x03, 11];
+        expected.extend_from_slice(b"db.internal");
+        expected.extend_from_slice(&443u16.to_be_bytes());
+        assert_eq!(handle.join().unwrap(), expected);
+    }
+
+    #[test]
+    fn sends_address_targets_as_addresses() {
+        let (proxy, handle) = socks_stub();
+        let target = SocketAddr::from(([192, 0, 2, 10], 22));
+        assert!(proxy
+            .open(target, "192.0.2.10", Duration::from_secs(2))
+            .is_ok());
+        assert_eq!(
+            handle.join().unwrap(),
+            vec![5, 1, 0, 0x01, 192, 0, 2, 10, 0, 22]
+        );
+
+        assert_eq!(host_name("[fd00::5]"), None);
+        assert_eq!(host_name(""), None);
+        assert_eq!(host_name(&"a".repeat(256)), None);
+        assert_eq!(host_name("example.com"), Some("example.com"));
+    }
+}
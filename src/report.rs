@@ -0,0 +1,284 @@
+use crate::findings::{Finding, Severity};
+use crate::html_report::render_html;
+use crate::markdown_report::render_markdown;
+use crate::sarif::render_sarif;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Html,
+    Markdown,
+    Sarif,
+}
+
+impl ReportFormat {
+    pub fn from_name(name: &str) -> Option<ReportFormat> {
+        match name.trim().to_lowercase().as_str() {
+            "html" | "htm" => Some(ReportFormat::Html),
+            "md" | "markdown" => Some(ReportFormat::Markdown),
+            "sarif" => Some(ReportFormat::Sarif),
+            _ => None,
+        }
+    }
+
+    /// Picks the format from an output file name such as `report.md`.
+    pub fn from_path(path: &str) -> Option<ReportFormat> {
+        path.rsplit_once('.')
+            .and_then(|(_, extension)| ReportFormat::from_name(extension))
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+            ReportFormat::Sarif => "sarif",
+        }
+    }
+
+    pub fn render(&self, reports: &[ScanReport], generated_at: &str) -> String {
+        match self {
+            ReportFormat::Html => render_html(reports, generated_at),
+            ReportFormat::Markdown => render_markdown(reports, generated_at),
+            ReportFormat::Sarif => {
+                let entries: Vec<(&str, &Finding)> = reports
+                    .iter()
+                    .flat_map(|r| r.findings.iter().map(move |f| (r.target.as_str(), f)))
+                    .collect();
+                render_sarif(&entries)
+            }
+        }
+    }
+}
+
+/// Everything a scan summary contains: what `write_log_summary` writes and
+/// what the exported reports are rendered from.
+#[derive(Debug, Clone, Default)]
+pub struct ScanReport {
+    pub scan_type: String,
+    pub target: String,
+    /// The reverse DNS name, empty without one.
+    pub host_name: String,
+    pub start_time: String,
+    pub end_time: String,
+    pub total_scanned: u32,
+    pub elapsed_secs: f32,
+    pub open_ports: Vec<u16>,
+    pub details: Vec<(u16, String)>,
+    pub findings: Vec<Finding>,
+}
+
+impl ScanReport {
+    pub fn details_for(&self, port: u16) -> Vec<&str> {
+        self.details
+            .iter()
+            .filter(|(p, _)| *p == port)
+            .map(|(_, details)| details.as_str())
+            .collect()
+    }
+
+    pub fn findings_for(&self, port: u16) -> Vec<&Finding> {
+        self.findings.iter().filter(|f| f.port == port).collect()
+    }
+
+    pub fn ports_per_second(&self) -> f32 {
+        if self.elapsed_secs > 0.0 {
+            self.total_scanned as f32 / self.elapsed_secs
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Section {
+    Header,
+    Summary,
+    OpenPorts,
+    Details,
+    Findings,
+}
+
+fn port_entry(line: &str) -> Option<(u16, &str)> {
+    let (port, rest) = line.trim().strip_prefix("Port ")?.split_once(": ")?;
+    Some((port.parse().ok()?, rest))
+}
+
+/// Rebuilds a report from a `.log` file written by an earlier scan.
+pub fn parse_log(contents: &str) -> Result<ScanReport, String> {
+    if !contents.contains("Vonogs Scanner Log") {
+        return Err(String::from("not a Vonogs scan log"));
+    }
+
+    let mut report = ScanReport::default();
+    let mut section = Section::Header;
+    let mut saw_summary = false;
+
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        match trimmed {
+            "Scan Summary" => {
+                section = Section::Summary;
+                saw_summary = true;
+                continue;
+            }
+            "Open Ports:" if saw_summary => {
+                section = Section::OpenPorts;
+                continue;
+            }
+            "Service Details:" if saw_summary => {
+                section = Section::Details;
+                continue;
+            }
+            "Findings:" if saw_summary => {
+                section = Section::Findings;
+                continue;
+            }
+            _ => {}
+        }
+
+        if let Some(value) = trimmed.strip_prefix("End Time: ") {
+            report.end_time = value.to_string();
+            continue;
+        }
+
+        match section {
+            Section::Header => {
+                if let Some(value) = trimmed.strip_prefix("Scan Type: ") {
+                    report.scan_type = value.to_string();
+                } else if let Some(value) = trimmed.strip_prefix("Target: ") {
+                    report.target = value.to_string();
+                } else if let Some(value) = trimmed.strip_prefix("Reverse DNS: ") {
+                    report.host_name = value.to_string();
+                } else if let Some(value) = trimmed.strip_prefix("Start Time: ") {
+                    report.start_time = value.to_string();
+                }
+            }
+            Section::Summary => {
+                if let Some(value) = trimmed.strip_prefix("Total Ports Scanned: ") {
+                    report.total_scanned = value.parse().unwrap_or(0);
+                } else if let Some(value) = trimmed.strip_prefix("Scan Duration: ") {
+                    report.elapsed_secs = value.trim_end_matches(" seconds").parse().unwrap_or(0.0);
+                }
+            }
+            Section::OpenPorts => {
+                if let Some((port, _)) = port_entry(trimmed) {
+                    report.open_ports.push(port);
+                }
+            }
+            Section::Details => {
+                if let Some((port, details)) = port_entry(trimmed) {
+                    report.details.push((port, details.to_string()));
+                }
+            }
+            Section::Findings => parse_finding_line(line, &mut report.findings),
+        }
+    }
+
+    if !saw_summary {
+        return Err(String::from("log has no scan summary (scan incomplete?)"));
+    }
+    Ok(report)
+}
+
+/// Findings are written as a `[SEVERITY] Port N: title` line followed by
+/// indented rule, description and remediation lines.
+fn parse_finding_line(line: &str, findings: &mut Vec<Finding>) {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return;
+    }
+
+    if let Some(rest) = trimmed.strip_prefix('[') {
+        if let Some((severity, rest)) = rest.split_once("] ") {
+            if let (Some(severity), Some((port, title))) =
+                (Severity::parse(severity), port_entry(rest))
+            {
+                findings.push(Finding {
+                    rule_id: String::new(),
+                    port,
+                    severity,
+                    title: title.to_string(),
+                    description: String::new(),
+                    remediation: String::new(),
+                });
+                return;
+            }
+        }
+    }
+
+    let finding = match findings.last_mut() {
+        Some(finding) => finding,
+        None => return,
+    };
+    if let Some(rule_id) = trimmed.strip_prefix("Rule: ") {
+        finding.rule_id = rule_id.to_string();
+    } else if let Some(remediation) = trimmed.strip_prefix("Remediation: ") {
+        finding.remediation = remediation.to_string();
+    } else if finding.description.is_empty() {
+        finding.description = trimmed.to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_LOG: &str = include_str!("../testdata/scan-example.log");
+
+    #[test]
+    fn parses_saved_log() {
+        let report = parse_log(EXAMPLE_LOG).unwrap();
+        assert_eq!(report.scan_type, "Port Range Scan");
+        assert_eq!(report.target, "web01.example.net");
+        assert_eq!(report.host_name, "web01.prod.example.net");
+        assert_eq!(report.start_time, "2026-10-19_09-15-02");
+        assert_eq!(report.end_time, "2026-10-19_09-15-05");
+        assert_eq!(report.total_scanned, 6);
+        assert_eq!(report.elapsed_secs, 2.45);
+
+        // Closed, filtered and denied ports stay out of the report.
+        assert_eq!(report.open_ports, vec![21, 80, 443]);
+        assert_eq!(
+            report.details_for(80),
+            vec!["HTTP 301 Moved Permanently | Server: nginx/1.24.0 | Location: https://web01.example.net/"]
+        );
+        assert!(report.details_for(443)[0].starts_with("TLSv1.0 | TLS_RSA_WITH_RC4_128_SHA"));
+        assert!(report.details_for(21).is_empty());
+
+        let findings: Vec<(&str, u16, Severity)> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule_id.as_str(), f.port, f.severity))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                ("ftp-exposed", 21, Severity::Medium),
+                ("tls-weak-negotiation", 443, Severity::Medium),
+                ("plain-http", 80, Severity::Low),
+            ]
+        );
+        let tls = report.findings_for(443)[0];
+        assert_eq!(tls.title, "Weak TLS protocol or cipher negotiated");
+        assert!(tls.description.starts_with("The server negotiated TLSv1.0"));
+        assert!(tls.remediation.starts_with("Require TLS 1.2 or newer"));
+        assert_eq!(
+            report.findings_for(80)[0].title,
+            "Plain HTTP (HTTP on port 80)"
+        );
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert_eq!(
+            parse_log("Port 22: OPEN\n").unwrap_err(),
+            "not a Vonogs scan log"
+        );
+        let unfinished = EXAMPLE_LOG
+            .split("\n=================================\nScan Summary")
+            .next();
+        assert_eq!(
+            parse_log(unfinished.unwrap()).unwrap_err(),
+            "log has no scan summary (scan incomplete?)"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use crate::dns;
+use crate::proxy;
+use crate::trace;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long a lookup is reused, from `VONOGS_DNS_TTL` in seconds; `0`
+/// resolves again for every scan. Unset, answers from `VONOGS_DNS_SERVER`
+/// keep their record TTL and the system resolver, which does not report
+/// it, gets 60 seconds.
+fn ttl_override() -> Option<Duration> {
+    std::env::var("VONOGS_DNS_TTL")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Whether `VONOGS_REVERSE_DNS` asks for the PTR name of every target.
+fn reverse_enabled() -> bool {
+    std::env::var("VONOGS_REVERSE_DNS").is_ok_and(|v| {
+        matches!(
+            v.trim().to_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        )
+    })
+}
+
+/// Where the addresses of a target came from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// The target was already an IP address.
+    Literal,
+    SystemResolver,
+    /// The built-in client, asking `VONOGS_DNS_SERVER`.
+    Server(SocketAddr),
+    /// Not resolvable here; the `--proxy` is asked for the name instead.
+    Proxy,
+}
+
+/// Every address a target resolved to, and how long they may be reused.
+#[derive(Debug, Clone)]
+pub struct Resolution {
+    pub target: String,
+    pub addrs: Vec<IpAddr>,
+    pub source: Source,
+    /// `None` for address literals, which never expire.
+    pub ttl: Option<Duration>,
+    pub resolved_at: Instant,
+    /// The PTR name of `ip()`, when reverse lookups are on and found one.
+    pub ptr: Option<String>,
+}
+
+impl Resolution {
+    /// The address scans connect to: the first IPv4 address, otherwise the
+    /// last IPv6 one. Targets left to the proxy have only `0.0.0.0`, which
+    /// the proxy never sees since it is sent the name.
+    pub fn ip(&self) -> IpAddr {
+        self.addrs
+            .iter()
+            .find(|ip| ip.is_ipv4())
+            .or_else(|| self.addrs.last())
+            .copied()
+            .expect("a resolution has at least one address")
+    }
+
+    pub fn socket_addr(&self, port: u16) -> SocketAddr {
+        SocketAddr::new(self.ip(), port)
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.ttl.is_none_or(|ttl| self.resolved_at.elapsed() < ttl)
+    }
+
+    /// `93.184.215.14 (also 2606:2800:21f:cb07:6820:80da:af6b:8b2c; system
+    /// resolver, cached for 60 s)`, as written to log headers.
+    pub fn describe(&self) -> String {
+        let ip = self.ip();
+        let others: Vec<String> = self
+            .addrs
+            .iter()
+            .filter(|a| **a != ip)
+            .map(IpAddr::to_string)
+            .collect();
+        let mut notes = Vec::new();
+        if !others.is_empty() {
+            notes.push(format!("also {}", others.join(", ")));
+        }
+        let source = match &self.source {
+            Source::Proxy => return String::from("by the proxy"),
+            Source::Literal => String::from("address literal"),
+            Source::SystemResolver => String::from("system resolver"),
+            Source::Server(server) => format!("DNS server {}", server),
+        };
+        match self.ttl {
+            Some(ttl) if !ttl.is_zero() => {
+                notes.push(format!("{}, cached for {} s", source, ttl.as_secs()))
+            }
+            _ => notes.push(source),
+        }
+        format!("{} ({})", ip, notes.join("; "))
+    }
+
+    /// The `Resolved:` and `Reverse DNS:` lines logs record for a target;
+    /// address literals without a PTR name get none.
+    pub fn log_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.source != Source::Literal {
+            lines.push(format!("Resolved: {}", self.describe()));
+        }
+        if let Some(ptr) = &self.ptr {
+            lines.push(format!("Reverse DNS: {}", ptr));
+        }
+        lines
+    }
+}
+
+fn cache() -> &'static Mutex<HashMap<String, Arc<Resolution>>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Arc<Resolution>>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn lookup(target: &str) -> Result<Resolution, String> {
+    if let Ok(ip) = target.parse::<IpAddr>() {
+        return Ok(Resolution {
+            target: target.to_string(),
+            addrs: vec![ip],
+            source: Source::Literal,
+            ttl: None,
+            resolved_at: Instant::now(),
+            ptr: None,
+        });
+    }
+
+    if let Ok(Some(server)) = dns::server_from_env() {
+        let (addrs, record_ttl) = dns::lookup(server, target)
+            .map_err(|e| format!("'{}' could not be resolved: {}", target, e))?;
+        return Ok(Resolution {
+            target: target.to_string(),
+            addrs,
+            source: Source::Server(server),
+            ttl: Some(ttl_override().unwrap_or(record_ttl)),
+            resolved_at: Instant::now(),
+            ptr: None,
+        });
+    }
+
+    let mut addrs: Vec<IpAddr> = Vec::new();
+    if let Ok(iter) = (target, 0).to_socket_addrs() {
+        for addr in iter {
+            if !addrs.contains(&addr.ip()) {
+                addrs.push(addr.ip());
+            }
+        }
+    }
+    if addrs.is_empty() {
+        return Err(format!("'{}' could not be resolved", target));
+    }
+    Ok(Resolution {
+        target: target.to_string(),
+        addrs,
+        source: Source::SystemResolver,
+        ttl: Some(ttl_override().unwrap_or(Duration::from_secs(60))),
+        resolved_at: Instant::now(),
+        ptr: None,
+    })
+}
+
+/// Resolves `target`, reusing an earlier answer until its TTL runs out.
+/// Scans call this once and connect to the returned addresses, so every
+/// port of a scan goes to the same host even if DNS changes meanwhile.
+/// Failures are not cached. With a `--proxy`, a name that does not resolve
+/// here is still scanned, by asking the proxy for it.
+pub fn resolve(target: &str) -> Result<Arc<Resolution>, String> {
+    resolve_for(target, proxy::current().is_some())
+}
+
+fn resolve_for(target: &str, proxied: bool) -> Result<Arc<Resolution>, String> {
+    let target = target.trim();
+    if let Some(cached) = cache()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(target)
+        .filter(|r| r.is_fresh())
+    {
+        trace::log(2, &format!("[vv] {}: cached {}", target, cached.describe()));
+        return Ok(Arc::clone(cached));
+    }
+
+    let mut resolution = match lookup(target) {
+        Ok(resolution) => resolution,
+        Err(e) if proxied && !target.is_empty() && target.len() <= 255 => {
+            trace::log(2, &format!("[vv] {}; leaving it to the proxy", e));
+            return Ok(Arc::new(Resolution {
+                target: target.to_string(),
+                addrs: vec![IpAddr::V4(Ipv4Addr::UNSPECIFIED)],
+                source: Source::Proxy,
+                ttl: None,
+                resolved_at: Instant::now(),
+                ptr: None,
+            }));
+        }
+        Err(e) => return Err(e),
+    };
+    if reverse_enabled() {
+        resolution.ptr =
+            dns::reverse_server().and_then(|server| dns::reverse(server, resolution.ip()));
+    }
+    let resolution = Arc::new(resolution);
+    trace::log(
+        2,
+        &format!("[vv] resolved {} to {}", target, resolution.describe()),
+    );
+    cache()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(target.to_string(), Arc::clone(&resolution));
+    Ok(resolution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::Proxy;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    // `.invalid` never resolves (RFC 6761).
+    const UNRESOLVABLE: &str = "db.bastion-only.invalid";
+
+    #[test]
+    fn fails_unresolvable_names_without_a_proxy() {
+        let error = resolve_for(UNRESOLVABLE, false).unwrap_err();
+        assert!(error.contains("could not be resolved"), "{}", error);
+    }
+
+    #[test]
+    fn proxies_names_that_do_not_resolve_locally() {
+        let resolution = resolve_for(UNRESOLVABLE, true).unwrap();
+        assert_eq!(resolution.source, Source::Proxy);
+        assert_eq!(resolution.log_lines(), vec!["Resolved: by the proxy"]);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy = Proxy::parse(&format!("socks5://{}", listener.local_addr().unwrap())).unwrap();
+        let stub = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).unwrap();
+            stream.write_all(&[5, 0x00]).unwrap();
+            let mut request = vec![0u8; 5 + UNRESOLVABLE.len() + 2];
+            stream.read_exact(&mut request).unwrap();
+            stream
+                .write_all(&[5, 0x00, 0, 1, 0, 0, 0, 0, 0, 0])
+                .unwrap();
+            request
+        });
+
+        assert!(proxy
+            .open(
+                resolution.socket_addr(5432),
+                &resolution.target,
+                Duration::from_secs(2)
+            )
+            .is_ok());
+        let mut expected = vec![5, 1, 0, 0x03, UNRESOLVABLE.len() as u8];
+        expected.extend_from_slice(UNRESOLVABLE.as_bytes());
+        expected.extend_from_slice(&5432u16.to_be_bytes());
+        assert_eq!(stub.join().unwrap(), expected);
+    }
+}
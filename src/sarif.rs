@@ -0,0 +1,117 @@
+use crate::findings::{Finding, Severity};
+use crate::json;
+use crate::services::get_service_name;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low | Severity::Info => "note",
+    }
+}
+
+/// Numeric score used by code-scanning dashboards to bucket results.
+fn security_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "9.5",
+        Severity::High => "8.0",
+        Severity::Medium => "5.5",
+        Severity::Low => "3.0",
+        Severity::Info => "0.0",
+    }
+}
+
+fn location(target: &str) -> String {
+    if target.contains(':') && !target.starts_with('[') {
+        format!("[{}]", target)
+    } else {
+        target.to_string()
+    }
+}
+
+fn text(value: &str) -> String {
+    format!("{{\"text\":{}}}", json::string(value))
+}
+
+fn rule_json(finding: &Finding) -> String {
+    let mut fields = vec![
+        format!("\"id\":{}", json::string(&finding.rule_id)),
+        format!("\"name\":{}", json::string(&finding.rule_id)),
+        format!("\"shortDescription\":{}", text(&finding.title)),
+    ];
+    if !finding.description.is_empty() {
+        fields.push(format!(
+            "\"fullDescription\":{}",
+            text(&finding.description)
+        ));
+    }
+    if !finding.remediation.is_empty() {
+        fields.push(format!("\"help\":{}", text(&finding.remediation)));
+    }
+    fields.push(format!(
+        "\"defaultConfiguration\":{{\"level\":{}}}",
+        json::string(level(finding.severity))
+    ));
+    fields.push(format!(
+        "\"properties\":{{\"security-severity\":{},\"tags\":[\"security\",\"network\"]}}",
+        json::string(security_severity(finding.severity))
+    ));
+    format!("{{{}}}", fields.join(","))
+}
+
+fn result_json(target: &str, finding: &Finding, rule_index: usize) -> String {
+    let host_port = format!("{}:{}", location(target), finding.port);
+    let mut message = finding.title.clone();
+    if !finding.description.is_empty() {
+        message.push_str(&format!(". {}", finding.description));
+    }
+    format!(
+        "{{\"ruleId\":{},\"ruleIndex\":{},\"level\":{},\"message\":{},\
+         \"locations\":[{{\"physicalLocation\":{{\"artifactLocation\":{{\"uri\":{}}}}},\
+         \"logicalLocations\":[{{\"name\":{},\"fullyQualifiedName\":{},\"kind\":\"resource\"}}]}}],\
+         \"partialFingerprints\":{{\"vonogsFinding/v1\":{}}},\
+         \"properties\":{{\"host\":{},\"port\":{},\"service\":{},\"severity\":{}}}}}",
+        json::string(&finding.rule_id),
+        rule_index,
+        json::string(level(finding.severity)),
+        text(&message),
+        json::string(&format!("tcp://{}", host_port)),
+        json::string(&host_port),
+        json::string(&format!("{}/{}", host_port, get_service_name(finding.port))),
+        json::string(&format!("{}|{}|{}", finding.rule_id, target, finding.port)),
+        json::string(target),
+        finding.port,
+        json::string(get_service_name(finding.port)),
+        json::string(finding.severity.label())
+    )
+}
+
+/// Renders findings as a single SARIF 2.1.0 run. Each entry pairs the
+/// scanned target with one of its findings; `host:port` is the location.
+pub fn render_sarif(entries: &[(&str, &Finding)]) -> String {
+    let mut rules: Vec<&Finding> = Vec::new();
+    let mut results = Vec::new();
+
+    for (target, finding) in entries {
+        let rule_index = match rules.iter().position(|r| r.rule_id == finding.rule_id) {
+            Some(index) => index,
+            None => {
+                rules.push(finding);
+                rules.len() - 1
+            }
+        };
+        results.push(result_json(target, finding, rule_index));
+    }
+
+    let rules: Vec<String> = rules.iter().map(|r| rule_json(r)).collect();
+    format!(
+        "{{\"$schema\":{},\"version\":\"2.1.0\",\"runs\":[{{\"tool\":{{\"driver\":{{\
+         \"name\":\"vonogs\",\"version\":{},\"rules\":[{}]}}}},\"results\":[{}]}}]}}\n",
+        json::string(SCHEMA),
+        json::string(env!("CARGO_PKG_VERSION")),
+        rules.join(","),
+        results.join(",")
+    )
+}
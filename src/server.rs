@@ -0,0 +1,606 @@
+use crate::engine::{abort_note, host_abort, scan_ports_until, PortResult, PortStatus};
+use crate::findings::{evaluate_rules, load_rules, sort_findings, Finding};
+use crate::json::{self, Value};
+use crate::policy::parse_port_list;
+use crate::profiles::ScanProfile;
+use crate::progress::Progress;
+use crate::resolve::resolve;
+use crate::services::get_service_name;
+use std::io::{Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8088";
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+const MAX_TARGETS: usize = 256;
+/// Scans queued or running at once; more are refused with `429`.
+const MAX_ACTIVE_SCANS: usize = 4;
+/// Finished scans whose results are kept; older ones are dropped.
+const MAX_FINISHED_SCANS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Cancelled)
+    }
+}
+
+/// What `POST /scans` asked for.
+#[derive(Debug, Clone)]
+pub struct ScanRequest {
+    pub targets: Vec<String>,
+    pub ports: Vec<u16>,
+    pub profile: Option<ScanProfile>,
+}
+
+struct HostResult {
+    target: String,
+    /// What the target resolved to when its scan started; empty if it
+    /// could not be resolved.
+    addresses: Vec<IpAddr>,
+    /// The reverse DNS name, when reverse lookups are on.
+    ptr: Option<String>,
+    /// Why the host could not be resolved or was given up on part-way,
+    /// e.g. host unreachable.
+    error: Option<String>,
+    results: Vec<PortResult>,
+    findings: Vec<Finding>,
+}
+
+struct ScanJob {
+    id: usize,
+    request: ScanRequest,
+    status: JobStatus,
+    ports_scanned: usize,
+    open_ports_found: usize,
+    created: String,
+    started: Option<String>,
+    finished: Option<String>,
+    hosts: Vec<HostResult>,
+    cancel: Arc<AtomicBool>,
+    progress: Progress,
+}
+
+impl ScanJob {
+    fn ports_total(&self) -> usize {
+        self.request.targets.len() * self.request.ports.len()
+    }
+
+    fn status_json(&self) -> String {
+        let targets: Vec<String> = self
+            .request
+            .targets
+            .iter()
+            .map(|t| json::string(t))
+            .collect();
+        let progress = match self.ports_total() {
+            0 => 100.0,
+            total => self.ports_scanned as f64 * 100.0 / total as f64,
+        };
+        let snapshot = self.progress.snapshot();
+        let eta = match (self.status, snapshot.eta) {
+            (JobStatus::Running, Some(eta)) => format!("{:.1}", eta.as_secs_f64()),
+            (JobStatus::Running, None) => String::from("null"),
+            _ => String::from("0"),
+        };
+        format!(
+            "{{\"id\":{},\"status\":{},\"targets\":[{}],\"profile\":{},\"ports_per_target\":{},\
+             \"ports_total\":{},\"ports_scanned\":{},\"progress\":{:.1},\"open_ports_found\":{},\
+             \"closed_ports\":{},\"filtered_ports\":{},\"unreachable_ports\":{},\"error_ports\":{},\
+             \"ports_per_second\":{:.1},\
+             \"elapsed_seconds\":{:.1},\"eta_seconds\":{},\
+             \"created\":{},\"started\":{},\"finished\":{}}}",
+            self.id,
+            json::string(self.status.label()),
+            targets.join(","),
+            json::optional_string(self.request.profile.map(|p| p.get_name())),
+            self.request.ports.len(),
+            self.ports_total(),
+            self.ports_scanned,
+            progress,
+            self.open_ports_found,
+            snapshot.closed,
+            snapshot.filtered,
+            snapshot.unreachable,
+            snapshot.errors,
+            snapshot.rate,
+            snapshot.elapsed.as_secs_f64(),
+            eta,
+            json::string(&self.created),
+            json::optional_string(self.started.as_deref()),
+            json::optional_string(self.finished.as_deref())
+        )
+    }
+
+    fn results_json(&self) -> String {
+        let hosts: Vec<String> = self
+            .hosts
+            .iter()
+            .map(|host| {
+                let open: Vec<String> = open_ports(&host.results)
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect();
+                let ports: Vec<String> = host
+                    .results
+                    .iter()
+                    .map(|r| {
+                        format!(
+                            "{{\"port\":{},\"service\":{},\"status\":{},\"reason\":{}}}",
+                            r.port,
+                            json::string(get_service_name(r.port)),
+                            json::string(r.status.label()),
+                            json::string(&r.reason.label())
+                        )
+                    })
+                    .collect();
+                let findings: Vec<String> = host.findings.iter().map(Finding::to_json).collect();
+                let addresses: Vec<String> = host
+                    .addresses
+                    .iter()
+                    .map(|ip| json::string(&ip.to_string()))
+                    .collect();
+                format!(
+                    "{{\"target\":{},\"resolved\":{},\"addresses\":[{}],\"ptr\":{},\"error\":{},\"open_ports\":[{}],\"ports\":[{}],\"findings\":[{}]}}",
+                    json::string(&host.target),
+                    !host.addresses.is_empty(),
+                    addresses.join(","),
+                    json::optional_string(host.ptr.as_deref()),
+                    json::optional_string(host.error.as_deref()),
+                    open.join(","),
+                    ports.join(","),
+                    findings.join(",")
+                )
+            })
+            .collect();
+        format!(
+            "{{\"id\":{},\"status\":{},\"hosts\":[{}]}}",
+            self.id,
+            json::string(self.status.label()),
+            hosts.join(",")
+        )
+    }
+}
+
+struct JobTable {
+    jobs: Vec<ScanJob>,
+    /// Ids keep counting up, so a pruned scan's id is never reused.
+    next_id: usize,
+}
+
+impl JobTable {
+    fn active(&self) -> usize {
+        self.jobs.iter().filter(|j| !j.status.is_finished()).count()
+    }
+
+    /// Drops the oldest finished scans beyond `MAX_FINISHED_SCANS`.
+    fn prune(&mut self) {
+        let finished = self.jobs.len() - self.active();
+        let mut excess = finished.saturating_sub(MAX_FINISHED_SCANS);
+        self.jobs.retain(|job| {
+            if excess > 0 && job.status.is_finished() {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+type Jobs = Arc<Mutex<JobTable>>;
+
+fn open_ports(results: &[PortResult]) -> Vec<u16> {
+    results
+        .iter()
+        .filter(|r| r.status == PortStatus::Open)
+        .map(|r| r.port)
+        .collect()
+}
+
+fn string_list(value: &Value, field: &str) -> Result<Vec<String>, String> {
+    match value {
+        Value::String(single) => Ok(vec![single.clone()]),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| format!("'{}' must contain strings", field))
+            })
+            .collect(),
+        _ => Err(format!("'{}' must be a string or an array", field)),
+    }
+}
+
+/// Accepts `{"targets": [...], "ports": [...] | "22,80,8000-8100", "profile": "web"}`.
+/// `target` may be used for a single host; ports and profile are combined.
+pub fn parse_scan_request(body: &str) -> Result<ScanRequest, String> {
+    let value = json::parse(body)?;
+    if !matches!(value, Value::Object(_)) {
+        return Err(String::from("request body must be a JSON object"));
+    }
+
+    let targets = match (value.get("targets"), value.get("target")) {
+        (Some(targets), _) => string_list(targets, "targets")?,
+        (None, Some(target)) => string_list(target, "target")?,
+        (None, None) => return Err(String::from("'targets' is required")),
+    };
+    let targets: Vec<String> = targets
+        .iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .collect();
+    if targets.is_empty() {
+        return Err(String::from("'targets' must not be empty"));
+    }
+    if targets.len() > MAX_TARGETS {
+        return Err(format!("at most {} targets per scan", MAX_TARGETS));
+    }
+
+    let profile = match value.get("profile") {
+        None | Some(Value::Null) => None,
+        Some(profile) => {
+            let name = profile.as_str().ok_or("'profile' must be a string")?;
+            Some(
+                ScanProfile::from_name(name)
+                    .ok_or_else(|| format!("unknown profile '{}'", name))?,
+            )
+        }
+    };
+
+    let mut ports = profile.map(|p| p.get_ports()).unwrap_or_default();
+    match value.get("ports") {
+        None | Some(Value::Null) => {}
+        Some(Value::String(list)) => ports.extend(parse_port_list(list)?),
+        Some(Value::Array(items)) => {
+            for item in items {
+                match item {
+                    Value::Number(n) if *n >= 1.0 && *n <= 65535.0 && n.fract() == 0.0 => {
+                        ports.push(*n as u16)
+                    }
+                    Value::String(list) => ports.extend(parse_port_list(list)?),
+                    _ => return Err(String::from("'ports' must contain port numbers or ranges")),
+                }
+            }
+        }
+        Some(_) => return Err(String::from("'ports' must be an array or a string")),
+    }
+    ports.sort_unstable();
+    ports.dedup();
+    if ports.is_empty() {
+        return Err(String::from("either 'ports' or 'profile' is required"));
+    }
+
+    Ok(ScanRequest {
+        targets,
+        ports,
+        profile,
+    })
+}
+
+/// Runs the API until the listener fails. Every scan runs on its own thread;
+/// `now` supplies the timestamps shown in job status.
+pub fn serve(listen: &str, now: fn() -> String) -> Result<(), String> {
+    let listener =
+        TcpListener::bind(listen).map_err(|e| format!("could not listen on {}: {}", listen, e))?;
+    let jobs: Jobs = Arc::new(Mutex::new(JobTable {
+        jobs: Vec::new(),
+        next_id: 1,
+    }));
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue,
+        };
+        let jobs = Arc::clone(&jobs);
+        thread::spawn(move || handle_connection(stream, jobs, now));
+    }
+    Ok(())
+}
+
+struct Request {
+    method: String,
+    path: String,
+    body: String,
+}
+
+fn read_request(stream: &mut TcpStream) -> Result<Request, (u16, String)> {
+    let bad_request = |message: &str| (400, message.to_string());
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+
+    let head_end = loop {
+        if let Some(idx) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break idx;
+        }
+        if data.len() > MAX_REQUEST_BYTES {
+            return Err((413, String::from("request too large")));
+        }
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => return Err(bad_request("incomplete request")),
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or("").split_whitespace();
+    let method = request_line
+        .next()
+        .ok_or_else(|| bad_request("missing method"))?;
+    let path = request_line
+        .next()
+        .ok_or_else(|| bad_request("missing path"))?;
+
+    let content_length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .map(|(_, value)| value.trim().parse::<usize>())
+        .transpose()
+        .map_err(|_| bad_request("invalid Content-Length"))?
+        .unwrap_or(0);
+    if content_length > MAX_REQUEST_BYTES {
+        return Err((413, String::from("request too large")));
+    }
+
+    let mut body = data[head_end + 4..].to_vec();
+    while body.len() < content_length {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => return Err(bad_request("incomplete body")),
+            Ok(n) => body.extend_from_slice(&buf[..n]),
+        }
+    }
+    body.truncate(content_length);
+
+    Ok(Request {
+        method: method.to_string(),
+        path: path.split('?').next().unwrap_or("").to_string(),
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        429 => "Too Many Requests",
+        _ => "Error",
+    }
+}
+
+fn error_json(message: &str) -> String {
+    format!("{{\"error\":{}}}", json::string(message))
+}
+
+fn handle_connection(mut stream: TcpStream, jobs: Jobs, now: fn() -> String) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(10)));
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(10)));
+
+    let (status, body, summary) = match read_request(&mut stream) {
+        Ok(request) => {
+            let (status, body) = route(&request, &jobs, now);
+            (status, body, format!("{} {}", request.method, request.path))
+        }
+        Err((status, message)) => (
+            status,
+            error_json(&message),
+            String::from("invalid request"),
+        ),
+    };
+    println!("[{}] {} -> {}", now(), summary, status);
+
+    let response = format!(
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        reason_phrase(status),
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+fn route(request: &Request, jobs: &Jobs, now: fn() -> String) -> (u16, String) {
+    let segments: Vec<&str> = request.path.split('/').filter(|s| !s.is_empty()).collect();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("POST", ["scans"]) => create_scan(&request.body, jobs, now),
+        (_, ["scans"]) => (405, error_json("use POST /scans")),
+        (method, ["scans", id]) | (method, ["scans", id, "results"]) => {
+            let id = match id.parse::<usize>() {
+                Ok(id) => id,
+                Err(_) => return (404, error_json("no such scan")),
+            };
+            let mut table = jobs.lock().unwrap();
+            let job = match table.jobs.iter_mut().find(|job| job.id == id) {
+                Some(job) => job,
+                None => return (404, error_json("no such scan")),
+            };
+            match (method, segments.len()) {
+                ("GET", 2) => (200, job.status_json()),
+                ("GET", 3) => (200, job.results_json()),
+                ("DELETE", 2) => cancel_scan(job),
+                _ => (405, error_json("method not allowed")),
+            }
+        }
+        _ => (404, error_json("not found")),
+    }
+}
+
+fn create_scan(body: &str, jobs: &Jobs, now: fn() -> String) -> (u16, String) {
+    let request = match parse_scan_request(body) {
+        Ok(request) => request,
+        Err(message) => return (400, error_json(&message)),
+    };
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let id = {
+        let mut table = jobs.lock().unwrap();
+        if table.active() >= MAX_ACTIVE_SCANS {
+            return (
+                429,
+                error_json(&format!(
+                    "{} scans are already running; retry when one finishes",
+                    MAX_ACTIVE_SCANS
+                )),
+            );
+        }
+        table.prune();
+        let id = table.next_id;
+        table.next_id += 1;
+        table.jobs.push(ScanJob {
+            id,
+            request: request.clone(),
+            status: JobStatus::Queued,
+            ports_scanned: 0,
+            open_ports_found: 0,
+            created: now(),
+            started: None,
+            finished: None,
+            hosts: Vec::new(),
+            cancel: Arc::clone(&cancel),
+            progress: Progress::new(request.targets.len() * request.ports.len()),
+        });
+        id
+    };
+
+    let worker_jobs = Arc::clone(jobs);
+    thread::spawn(move || run_scan(id, request, cancel, worker_jobs, now));
+
+    (
+        202,
+        format!(
+            "{{\"id\":{},\"status\":\"queued\",\"status_url\":\"/scans/{}\",\"results_url\":\"/scans/{}/results\"}}",
+            id, id, id
+        ),
+    )
+}
+
+fn cancel_scan(job: &mut ScanJob) -> (u16, String) {
+    if job.status.is_finished() {
+        return (
+            409,
+            error_json(&format!("scan is already {}", job.status.label())),
+        );
+    }
+    job.cancel.store(true, Ordering::Relaxed);
+    (
+        200,
+        format!("{{\"id\":{},\"status\":\"cancelling\"}}", job.id),
+    )
+}
+
+fn with_job(jobs: &Jobs, id: usize, update: impl FnOnce(&mut ScanJob)) {
+    if let Some(job) = jobs
+        .lock()
+        .unwrap()
+        .jobs
+        .iter_mut()
+        .find(|job| job.id == id)
+    {
+        update(job);
+    }
+}
+
+fn run_scan(
+    id: usize,
+    request: ScanRequest,
+    cancel: Arc<AtomicBool>,
+    jobs: Jobs,
+    now: fn() -> String,
+) {
+    with_job(&jobs, id, |job| {
+        job.status = JobStatus::Running;
+        job.started = Some(now());
+    });
+    let (rules, _) = load_rules();
+
+    for target in &request.targets {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let (resolution, resolve_error) = match resolve(target) {
+            Ok(resolution) => (Some(resolution), None),
+            Err(message) => (None, Some(message)),
+        };
+        let results = if let Some(resolution) = &resolution {
+            scan_ports_until(resolution, &request.ports, &cancel, |_, result| {
+                with_job(&jobs, id, |job| {
+                    job.ports_scanned += 1;
+                    job.progress.record(result.status);
+                    if result.status == PortStatus::Open {
+                        job.open_ports_found += 1;
+                    }
+                });
+            })
+        } else {
+            with_job(&jobs, id, |job| {
+                job.ports_scanned += request.ports.len();
+                job.progress.skip(request.ports.len());
+            });
+            Vec::new()
+        };
+
+        let error = resolve_error.or_else(|| {
+            host_abort(&results, request.ports.len()).map(|status| {
+                let skipped = request.ports.len() - results.len();
+                with_job(&jobs, id, |job| {
+                    job.ports_scanned += skipped;
+                    job.progress.skip(skipped);
+                });
+                abort_note(status, results.len(), request.ports.len())
+            })
+        });
+        let mut findings = evaluate_rules(&rules, &open_ports(&results));
+        sort_findings(&mut findings);
+        let host = HostResult {
+            target: target.clone(),
+            addresses: resolution
+                .as_ref()
+                .map_or_else(Vec::new, |r| r.addrs.clone()),
+            ptr: resolution.and_then(|r| r.ptr.clone()),
+            error,
+            results,
+            findings,
+        };
+        with_job(&jobs, id, |job| job.hosts.push(host));
+    }
+
+    with_job(&jobs, id, |job| {
+        job.status = if cancel.load(Ordering::Relaxed) {
+            JobStatus::Cancelled
+        } else {
+            JobStatus::Completed
+        };
+        job.finished = Some(now());
+        job.progress.stop();
+    });
+}
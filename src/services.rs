@@ -152,3 +152,10 @@ pub fn get_service_name(port: u16) -> &'static str {
         _ => "Unknown",
     }
 }
+
+pub fn is_tls_port(port: u16) -> bool {
+    matches!(
+        port,
+        443 | 465 | 636 | 990 | 993 | 995 | 2083 | 2087 | 4443 | 5061 | 5986 | 7001 | 8443 | 8883
+    )
+}
@@ -0,0 +1,246 @@
+use crate::proxy;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+const CLIENT_IDENTIFICATION: &[u8] = b"SSH-2.0-vonogs_sentinel\r\n";
+const SSH_MSG_KEXINIT: u8 = 20;
+const MAX_IDENTIFICATION_LINES: usize = 20;
+const MAX_PACKET_LENGTH: usize = 64 * 1024;
+
+const WEAK_KEX: &[&str] = &[
+    "diffie-hellman-group1-sha1",
+    "diffie-hellman-group14-sha1",
+    "diffie-hellman-group-exchange-sha1",
+    "rsa1024-sha1",
+];
+const WEAK_HOST_KEYS: &[&str] = &["ssh-dss", "ssh-rsa", "ssh-rsa-cert-v01@openssh.com"];
+const WEAK_MACS: &[&str] = &[
+    "hmac-md5",
+    "hmac-md5-96",
+    "hmac-md5-etm@openssh.com",
+    "hmac-md5-96-etm@openssh.com",
+    "hmac-sha1",
+    "hmac-sha1-96",
+    "hmac-sha1-etm@openssh.com",
+    "hmac-sha1-96-etm@openssh.com",
+    "umac-64@openssh.com",
+    "umac-64-etm@openssh.com",
+];
+
+#[derive(Debug, Clone)]
+pub struct SshInfo {
+    pub identification: String,
+    pub protocol_version: String,
+    pub software: String,
+    pub kex_algorithms: Vec<String>,
+    pub host_key_algorithms: Vec<String>,
+    pub ciphers_client_to_server: Vec<String>,
+    pub ciphers_server_to_client: Vec<String>,
+    pub macs_client_to_server: Vec<String>,
+    pub macs_server_to_client: Vec<String>,
+    pub compression: Vec<String>,
+}
+
+impl SshInfo {
+    pub fn describe(&self) -> String {
+        format!("{} (protocol {})", self.software, self.protocol_version)
+    }
+
+    /// Offered algorithms grouped by category, in the order the server sent them.
+    pub fn algorithm_lists(&self) -> Vec<(&'static str, &Vec<String>)> {
+        vec![
+            ("kex", &self.kex_algorithms),
+            ("host-key", &self.host_key_algorithms),
+            ("cipher client-to-server", &self.ciphers_client_to_server),
+            ("cipher server-to-client", &self.ciphers_server_to_client),
+            ("mac client-to-server", &self.macs_client_to_server),
+            ("mac server-to-client", &self.macs_server_to_client),
+            ("compression", &self.compression),
+        ]
+    }
+
+    pub fn weaknesses(&self) -> Vec<String> {
+        let mut weaknesses = Vec::new();
+        if self.protocol_version != "2.0" {
+            weaknesses.push(format!("protocol {} supported", self.protocol_version));
+        }
+        for kex in &self.kex_algorithms {
+            if WEAK_KEX.contains(&kex.as_str()) {
+                weaknesses.push(format!("kex: {}", kex));
+            }
+        }
+        for host_key in &self.host_key_algorithms {
+            if WEAK_HOST_KEYS.contains(&host_key.as_str()) {
+                weaknesses.push(format!("host-key: {}", host_key));
+            }
+        }
+        weaknesses.extend(directional_weaknesses(
+            "cipher",
+            &self.ciphers_client_to_server,
+            &self.ciphers_server_to_client,
+            is_weak_cipher,
+        ));
+        weaknesses.extend(directional_weaknesses(
+            "mac",
+            &self.macs_client_to_server,
+            &self.macs_server_to_client,
+            |mac| WEAK_MACS.contains(&mac),
+        ));
+        weaknesses
+    }
+}
+
+/// Weak algorithms from both directions of a category. One offered both
+/// ways is listed once; otherwise the direction it is offered in is named.
+fn directional_weaknesses(
+    category: &str,
+    client_to_server: &[String],
+    server_to_client: &[String],
+    is_weak: impl Fn(&str) -> bool,
+) -> Vec<String> {
+    let mut weaknesses = Vec::new();
+    for name in client_to_server.iter().filter(|n| is_weak(n)) {
+        if server_to_client.contains(name) {
+            weaknesses.push(format!("{}: {}", category, name));
+        } else {
+            weaknesses.push(format!("{} client-to-server: {}", category, name));
+        }
+    }
+    for name in server_to_client
+        .iter()
+        .filter(|n| is_weak(n) && !client_to_server.contains(n))
+    {
+        weaknesses.push(format!("{} server-to-client: {}", category, name));
+    }
+    weaknesses
+}
+
+fn is_weak_cipher(cipher: &str) -> bool {
+    cipher.ends_with("-cbc")
+        || cipher.starts_with("arcfour")
+        || cipher == "none"
+        || cipher == "rijndael-cbc@lysator.liu.se"
+}
+
+pub fn is_ssh_service(service_name: &str) -> bool {
+    matches!(service_name, "SSH" | "SSH-ALT")
+}
+
+pub fn inspect_ssh(addr: SocketAddr, host: &str, timeout: Duration) -> Result<SshInfo, String> {
+    let mut stream =
+        proxy::connect(addr, host, timeout).map_err(|e| format!("connect failed: {}", e))?;
+    let io_timeout = timeout.max(Duration::from_secs(3));
+    let _ = stream.set_read_timeout(Some(io_timeout));
+    let _ = stream.set_write_timeout(Some(io_timeout));
+
+    let identification = read_identification(&mut stream)?;
+    stream
+        .write_all(CLIENT_IDENTIFICATION)
+        .map_err(|e| format!("write failed: {}", e))?;
+
+    let (protocol_version, software) = parse_identification(&identification)?;
+    let payload = read_packet(&mut stream)?;
+    if payload.first() != Some(&SSH_MSG_KEXINIT) {
+        return Err(String::from("server did not send KEXINIT"));
+    }
+
+    // Skip the message type and the 16 byte cookie.
+    let mut lists = NameLists {
+        data: payload.get(17..).ok_or("truncated KEXINIT")?,
+    };
+    let kex_algorithms = lists.next()?;
+    let host_key_algorithms = lists.next()?;
+    let ciphers_client_to_server = lists.next()?;
+    let ciphers_server_to_client = lists.next()?;
+    let macs_client_to_server = lists.next()?;
+    let macs_server_to_client = lists.next()?;
+    let compression = lists.next()?;
+
+    Ok(SshInfo {
+        identification,
+        protocol_version,
+        software,
+        kex_algorithms,
+        host_key_algorithms,
+        ciphers_client_to_server,
+        ciphers_server_to_client,
+        macs_client_to_server,
+        macs_server_to_client,
+        compression,
+    })
+}
+
+/// Servers may send banner lines before the `SSH-` identification string.
+fn read_identification(stream: &mut TcpStream) -> Result<String, String> {
+    for _ in 0..MAX_IDENTIFICATION_LINES {
+        let mut line = Vec::new();
+        let mut byte = [0u8; 1];
+        loop {
+            match stream.read(&mut byte) {
+                Ok(0) => return Err(String::from("connection closed")),
+                Ok(_) if byte[0] == b'\n' => break,
+                Ok(_) => line.push(byte[0]),
+                Err(_) => return Err(String::from("no SSH identification received")),
+            }
+            if line.len() > 255 {
+                return Err(String::from("identification line too long"));
+            }
+        }
+
+        let line = String::from_utf8_lossy(&line).trim_end().to_string();
+        if line.starts_with("SSH-") {
+            return Ok(line);
+        }
+    }
+    Err(String::from("no SSH identification received"))
+}
+
+fn parse_identification(identification: &str) -> Result<(String, String), String> {
+    let rest = identification
+        .strip_prefix("SSH-")
+        .ok_or("invalid identification")?;
+    let (protocol_version, software) = rest.split_once('-').ok_or("invalid identification")?;
+    Ok((protocol_version.to_string(), software.to_string()))
+}
+
+fn read_packet(stream: &mut TcpStream) -> Result<Vec<u8>, String> {
+    let mut length = [0u8; 4];
+    stream
+        .read_exact(&mut length)
+        .map_err(|_| String::from("no KEXINIT received"))?;
+    let length = u32::from_be_bytes(length) as usize;
+    if !(5..=MAX_PACKET_LENGTH).contains(&length) {
+        return Err(String::from("invalid packet length"));
+    }
+
+    let mut packet = vec![0u8; length];
+    stream
+        .read_exact(&mut packet)
+        .map_err(|_| String::from("truncated KEXINIT"))?;
+    let padding = packet[0] as usize;
+    let payload_end = length
+        .checked_sub(padding)
+        .filter(|end| *end > 1)
+        .ok_or("invalid packet padding")?;
+    Ok(packet[1..payload_end].to_vec())
+}
+
+struct NameLists<'a> {
+    data: &'a [u8],
+}
+
+impl NameLists<'_> {
+    fn next(&mut self) -> Result<Vec<String>, String> {
+        let length = self.data.get(..4).ok_or("truncated KEXINIT")?;
+        let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]) as usize;
+        let list = self.data.get(4..4 + length).ok_or("truncated KEXINIT")?;
+        self.data = &self.data[4 + length..];
+
+        Ok(String::from_utf8_lossy(list)
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(String::from)
+            .collect())
+    }
+}
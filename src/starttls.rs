@@ -0,0 +1,336 @@
+use crate::proxy;
+use crate::tls::{handshake, TlsInfo};
+use crate::x509::Der;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+const MAX_RESPONSE_BYTES: usize = 16 * 1024;
+
+// LDAPMessage { messageID 1, ExtendedRequest { requestName 1.3.6.1.4.1.1466.20037 } }
+const LDAP_STARTTLS_REQUEST: &[u8] = b"\x30\x1d\x02\x01\x01\x77\x18\x80\x161.3.6.1.4.1.1466.20037";
+
+#[derive(Debug, Clone, Copy)]
+enum Protocol {
+    Ftp,
+    Smtp,
+    Pop3,
+    Imap,
+    Ldap,
+}
+
+impl Protocol {
+    fn for_port(port: u16) -> Option<Protocol> {
+        match port {
+            21 => Some(Protocol::Ftp),
+            25 | 587 => Some(Protocol::Smtp),
+            110 => Some(Protocol::Pop3),
+            143 => Some(Protocol::Imap),
+            389 => Some(Protocol::Ldap),
+            _ => None,
+        }
+    }
+
+    fn command_name(&self) -> &'static str {
+        match self {
+            Protocol::Ftp => "AUTH TLS",
+            Protocol::Pop3 => "STLS",
+            Protocol::Smtp | Protocol::Imap | Protocol::Ldap => "STARTTLS",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StartTlsInfo {
+    pub command: &'static str,
+    pub supported: bool,
+    pub tls: Option<Result<TlsInfo, String>>,
+}
+
+impl StartTlsInfo {
+    pub fn describe(&self) -> String {
+        if !self.supported {
+            return format!("supports {}: no (plaintext only)", self.command);
+        }
+
+        let mut description = format!("supports {}: yes", self.command);
+        match &self.tls {
+            Some(Ok(info)) => description.push_str(&format!(" | {}", info.describe())),
+            Some(Err(reason)) => {
+                description.push_str(&format!(" | TLS handshake failed ({})", reason))
+            }
+            None => {}
+        }
+        description
+    }
+}
+
+pub fn is_starttls_port(port: u16) -> bool {
+    Protocol::for_port(port).is_some()
+}
+
+pub fn inspect_starttls(
+    addr: SocketAddr,
+    server_name: &str,
+    timeout: Duration,
+) -> Result<StartTlsInfo, String> {
+    let protocol = Protocol::for_port(addr.port()).ok_or("no STARTTLS probe for this port")?;
+    let mut stream =
+        proxy::connect(addr, server_name, timeout).map_err(|e| format!("connect failed: {}", e))?;
+    let io_timeout = timeout.max(Duration::from_secs(3));
+    let _ = stream.set_read_timeout(Some(io_timeout));
+    let _ = stream.set_write_timeout(Some(io_timeout));
+
+    let supported = match protocol {
+        Protocol::Ftp => negotiate_ftp(&mut stream)?,
+        Protocol::Smtp => negotiate_smtp(&mut stream)?,
+        Protocol::Pop3 => negotiate_pop3(&mut stream)?,
+        Protocol::Imap => negotiate_imap(&mut stream)?,
+        Protocol::Ldap => negotiate_ldap(&mut stream)?,
+    };
+
+    let tls = if supported {
+        Some(handshake(&mut stream, server_name))
+    } else {
+        None
+    };
+
+    Ok(StartTlsInfo {
+        command: protocol.command_name(),
+        supported,
+        tls,
+    })
+}
+
+fn send(stream: &mut TcpStream, command: &str) -> Result<(), String> {
+    stream
+        .write_all(command.as_bytes())
+        .map_err(|e| format!("write failed: {}", e))
+}
+
+/// Reads from the stream until `is_complete` accepts the text received so far.
+fn read_until(
+    stream: &mut TcpStream,
+    is_complete: impl Fn(&str) -> bool,
+) -> Result<String, String> {
+    let mut response = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) => return Err(String::from("connection closed")),
+            Ok(n) => response.extend_from_slice(&buf[..n]),
+            Err(_) => return Err(String::from("no response")),
+        }
+        let text = String::from_utf8_lossy(&response);
+        if is_complete(&text) {
+            return Ok(text.to_string());
+        }
+        if response.len() > MAX_RESPONSE_BYTES {
+            return Err(String::from("response too large"));
+        }
+    }
+}
+
+fn last_line(text: &str) -> &str {
+    text.trim_end_matches("\r\n")
+        .rsplit("\r\n")
+        .next()
+        .unwrap_or("")
+}
+
+/// FTP and SMTP replies end with a line of the form `NNN text`.
+fn reply_complete(text: &str) -> bool {
+    text.ends_with("\r\n") && last_line(text).as_bytes().get(3).is_none_or(|b| *b == b' ')
+}
+
+fn reply_code(text: &str) -> &str {
+    last_line(text).get(..3).unwrap_or("")
+}
+
+fn negotiate_ftp(stream: &mut TcpStream) -> Result<bool, String> {
+    let greeting = read_until(stream, reply_complete)?;
+    if reply_code(&greeting) != "220" {
+        return Err(String::from("unexpected FTP greeting"));
+    }
+    send(stream, "AUTH TLS\r\n")?;
+    let reply = read_until(stream, reply_complete)?;
+    Ok(reply_code(&reply) == "234")
+}
+
+fn negotiate_smtp(stream: &mut TcpStream) -> Result<bool, String> {
+    let greeting = read_until(stream, reply_complete)?;
+    if reply_code(&greeting) != "220" {
+        return Err(String::from("unexpected SMTP greeting"));
+    }
+    send(stream, "EHLO vonogs.local\r\n")?;
+    let ehlo = read_until(stream, reply_complete)?;
+    let advertised = ehlo.lines().any(|line| {
+        line.get(4..)
+            .is_some_and(|ext| ext.trim().eq_ignore_ascii_case("STARTTLS"))
+    });
+    if !advertised {
+        return Ok(false);
+    }
+    send(stream, "STARTTLS\r\n")?;
+    let reply = read_until(stream, reply_complete)?;
+    Ok(reply_code(&reply) == "220")
+}
+
+fn negotiate_pop3(stream: &mut TcpStream) -> Result<bool, String> {
+    let greeting = read_until(stream, |text| text.ends_with("\r\n"))?;
+    if !greeting.starts_with("+OK") {
+        return Err(String::from("unexpected POP3 greeting"));
+    }
+    send(stream, "CAPA\r\n")?;
+    let capabilities = read_until(stream, |text| {
+        (text.starts_with("-ERR") && text.ends_with("\r\n")) || text.ends_with("\r\n.\r\n")
+    })?;
+    let advertised = capabilities
+        .lines()
+        .any(|line| line.trim().eq_ignore_ascii_case("STLS"));
+    if !advertised {
+        return Ok(false);
+    }
+    send(stream, "STLS\r\n")?;
+    let reply = read_until(stream, |text| text.ends_with("\r\n"))?;
+    Ok(reply.starts_with("+OK"))
+}
+
+fn tagged_reply_complete(tag: &'static str) -> impl Fn(&str) -> bool {
+    move |text: &str| text.ends_with("\r\n") && last_line(text).starts_with(tag)
+}
+
+fn negotiate_imap(stream: &mut TcpStream) -> Result<bool, String> {
+    let greeting = read_until(stream, |text| text.ends_with("\r\n"))?;
+    if !greeting.starts_with("* OK") {
+        return Err(String::from("unexpected IMAP greeting"));
+    }
+    send(stream, "a1 CAPABILITY\r\n")?;
+    let capabilities = read_until(stream, tagged_reply_complete("a1 "))?;
+    let advertised = capabilities
+        .split_whitespace()
+        .any(|word| word.eq_ignore_ascii_case("STARTTLS"));
+    if !advertised {
+        return Ok(false);
+    }
+    send(stream, "a2 STARTTLS\r\n")?;
+    let reply = read_until(stream, tagged_reply_complete("a2 "))?;
+    Ok(last_line(&reply).starts_with("a2 OK"))
+}
+
+fn negotiate_ldap(stream: &mut TcpStream) -> Result<bool, String> {
+    stream
+        .write_all(LDAP_STARTTLS_REQUEST)
+        .map_err(|e| format!("write failed: {}", e))?;
+
+    // Read until one whole LDAPMessage has arrived.
+    let mut response = Vec::new();
+    let mut buf = [0u8; 1024];
+    let len = loop {
+        if let Some((_, _, message)) = Der::new(&response).next() {
+            break message.len();
+        }
+        if response.len() > MAX_RESPONSE_BYTES {
+            return Err(String::from("response too large"));
+        }
+        match stream.read(&mut buf) {
+            Ok(0) => return Err(String::from("connection closed")),
+            Ok(n) => response.extend_from_slice(&buf[..n]),
+            Err(_) => return Err(String::from("no LDAP response")),
+        }
+    };
+    let result = ldap_result_code(&response[..len]).ok_or("unexpected LDAP response")?;
+    Ok(result == 0)
+}
+
+/// The resultCode of the ExtendedResponse to the StartTLS request:
+/// LDAPMessage { messageID 1, [APPLICATION 24] { resultCode, ... } }.
+/// `None` for anything else, such as a notice of disconnection.
+fn ldap_result_code(message: &[u8]) -> Option<u32> {
+    let mut message = Der::new(Der::new(message).expect(0x30)?);
+    if message.expect(0x02)? != [1] {
+        return None;
+    }
+    let mut response = Der::new(message.expect(0x78)?);
+    let code = response.expect(0x0a)?;
+    if code.is_empty() || code.len() > 4 {
+        return None;
+    }
+    Some(
+        code.iter()
+            .fold(0, |code, byte| code << 8 | u32::from(*byte)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// An LDAPMessage with `id` around an ExtendedResponse whose fields
+    /// follow the resultCode.
+    fn extended_response(id: u8, result_code: u8, rest: &[u8]) -> Vec<u8> {
+        let mut op = vec![0x0a, 0x01, result_code];
+        op.extend_from_slice(rest);
+        let mut body = vec![0x02, 0x01, id, 0x78, op.len() as u8];
+        body.extend_from_slice(&op);
+        let mut message = vec![0x30, body.len() as u8];
+        message.extend_from_slice(&body);
+        message
+    }
+
+    #[test]
+    fn decodes_ldap_result_codes() {
+        // matchedDN and diagnosticMessage, both empty.
+        let success = extended_response(1, 0, &[0x04, 0x00, 0x04, 0x00]);
+        assert_eq!(ldap_result_code(&success), Some(0));
+
+        // unwillingToPerform, with a diagnostic that looks like a success.
+        let mut diagnostic = vec![0x04, 0x00, 0x04, 0x05];
+        diagnostic.extend_from_slice(b"\x0a\x01\x00ok");
+        let refused = extended_response(1, 53, &diagnostic);
+        assert_eq!(ldap_result_code(&refused), Some(53));
+
+        // A long-form length is still one message.
+        let mut long_form = vec![0x30, 0x81, success.len() as u8 - 2];
+        long_form.extend_from_slice(&success[2..]);
+        assert_eq!(ldap_result_code(&long_form), Some(0));
+    }
+
+    #[test]
+    fn rejects_other_ldap_messages() {
+        // A notice of disconnection arrives with messageID 0.
+        assert_eq!(ldap_result_code(&extended_response(0, 0, &[])), None);
+        // A BindResponse is [APPLICATION 1].
+        let mut bind = extended_response(1, 0, &[]);
+        bind[5] = 0x61;
+        assert_eq!(ldap_result_code(&bind), None);
+        let truncated = extended_response(1, 0, &[0x04, 0x00]);
+        assert_eq!(ldap_result_code(&truncated[..truncated.len() - 1]), None);
+    }
+
+    #[test]
+    fn negotiates_ldap_starttls() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = vec![0u8; LDAP_STARTTLS_REQUEST.len()];
+            stream.read_exact(&mut request).unwrap();
+            // Sent in two parts, so the reply has to be reassembled.
+            let response = extended_response(1, 0, &[0x04, 0x00, 0x04, 0x00]);
+            stream.write_all(&response[..4]).unwrap();
+            thread::sleep(Duration::from_millis(50));
+            stream.write_all(&response[4..]).unwrap();
+            request
+        });
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        assert_eq!(negotiate_ldap(&mut stream), Ok(true));
+        assert_eq!(server.join().unwrap(), LDAP_STARTTLS_REQUEST);
+    }
+}
@@ -0,0 +1,466 @@
+use crate::proxy;
+use crate::x509::{now_unix, parse_certificate, CertificateInfo};
+use std::io::{Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const CONTENT_ALERT: u8 = 21;
+const CONTENT_HANDSHAKE: u8 = 22;
+
+const HANDSHAKE_SERVER_HELLO: u8 = 2;
+const HANDSHAKE_CERTIFICATE: u8 = 11;
+const HANDSHAKE_SERVER_HELLO_DONE: u8 = 14;
+
+const MAX_RECORDS: usize = 32;
+
+// Modern suites first so the server's normal preference is what gets
+// reported; legacy suites are still offered so old servers answer at all.
+const OFFERED_CIPHER_SUITES: &[u16] = &[
+    0xc02b, 0xc02f, 0xc02c, 0xc030, 0xcca9, 0xcca8, 0x009e, 0x009f, 0xc009, 0xc013, 0xc00a, 0xc014,
+    0xc023, 0xc027, 0xc024, 0xc028, 0x0033, 0x0039, 0x009c, 0x009d, 0x003c, 0x003d, 0x002f, 0x0035,
+    0xc012, 0x0016, 0x000a, 0x0005, 0x0004,
+];
+
+const WEAK_VERSIONS: &[&str] = &["SSLv3", "TLSv1.0", "TLSv1.1"];
+
+// Parts of a suite name that mean a broken cipher, hash or key exchange.
+const WEAK_SUITE_PARTS: &[&str] = &[
+    "_RC4_", "_DES_", "_3DES_", "_NULL_", "_EXPORT", "_anon_", "_MD5",
+];
+
+#[derive(Debug, Clone)]
+pub struct TlsInfo {
+    pub version: String,
+    pub cipher_suite: String,
+    pub certificate: Option<CertificateInfo>,
+}
+
+impl TlsInfo {
+    pub fn describe(&self) -> String {
+        let mut parts = vec![format!("{} {}", self.version, self.cipher_suite)];
+        if let Some(cert) = &self.certificate {
+            parts.push(format!("Subject: {}", cert.subject));
+            parts.push(format!("Issuer: {}", cert.issuer));
+            if !cert.sans.is_empty() {
+                parts.push(format!("SANs: {}", cert.sans.join(", ")));
+            }
+            parts.push(format!(
+                "Expires: {} ({} days)",
+                cert.not_after,
+                cert.days_until_expiry()
+            ));
+        }
+        parts.join(" | ")
+    }
+
+    pub fn warnings(&self, expiry_warning_days: i64) -> Vec<String> {
+        self.warnings_at(expiry_warning_days, now_unix())
+    }
+
+    /// The warnings as of `now`, in Unix seconds.
+    pub fn warnings_at(&self, expiry_warning_days: i64, now: i64) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if let Some(cert) = &self.certificate {
+            let days_left = cert.days_until_expiry_at(now);
+            if days_left < 0 {
+                warnings.push(String::from("EXPIRED"));
+            } else if days_left <= expiry_warning_days {
+                warnings.push(format!("EXPIRES IN {} DAYS", days_left));
+            }
+            if cert.self_signed {
+                warnings.push(String::from("SELF-SIGNED"));
+            }
+        }
+        warnings
+    }
+
+    /// Weak parts of what the server chose: a protocol older than TLS 1.2
+    /// or a suite with a broken cipher. Since modern suites are offered
+    /// first, a weak suite means the server prefers or only has it.
+    pub fn weaknesses(&self) -> Vec<String> {
+        let mut weaknesses = Vec::new();
+        if WEAK_VERSIONS.contains(&self.version.as_str()) {
+            weaknesses.push(format!("protocol: {}", self.version));
+        }
+        if WEAK_SUITE_PARTS
+            .iter()
+            .any(|part| self.cipher_suite.contains(part))
+        {
+            weaknesses.push(format!("cipher: {}", self.cipher_suite));
+        }
+        weaknesses
+    }
+}
+
+/// Days before expiry at which a certificate gets flagged.
+pub fn expiry_warning_days() -> i64 {
+    std::env::var("VONOGS_TLS_EXPIRY_DAYS")
+        .ok()
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .unwrap_or(30)
+}
+
+pub fn inspect_tls(
+    addr: SocketAddr,
+    server_name: &str,
+    timeout: Duration,
+) -> Result<TlsInfo, String> {
+    let mut stream =
+        proxy::connect(addr, server_name, timeout).map_err(|e| format!("connect failed: {}", e))?;
+    let io_timeout = timeout.max(Duration::from_secs(3));
+    let _ = stream.set_read_timeout(Some(io_timeout));
+    let _ = stream.set_write_timeout(Some(io_timeout));
+    handshake(&mut stream, server_name)
+}
+
+/// Runs a TLS handshake on an already connected stream up to the point where
+/// the server certificate is known. The connection is not usable afterwards.
+pub fn handshake(stream: &mut TcpStream, server_name: &str) -> Result<TlsInfo, String> {
+    stream
+        .write_all(&client_hello(server_name))
+        .map_err(|e| format!("write failed: {}", e))?;
+
+    let mut handshake_data: Vec<u8> = Vec::new();
+    let mut server_hello: Option<(u16, u16)> = None;
+    let mut certificate: Option<CertificateInfo> = None;
+
+    for _ in 0..MAX_RECORDS {
+        let (content_type, payload) = read_record(stream)?;
+        match content_type {
+            CONTENT_ALERT => {
+                let description = payload.get(1).copied().unwrap_or(0);
+                return Err(format!("server sent alert: {}", alert_name(description)));
+            }
+            CONTENT_HANDSHAKE => handshake_data.extend_from_slice(&payload),
+            _ => return Err(String::from("unexpected record type")),
+        }
+
+        let mut finished = false;
+        while handshake_data.len() >= 4 {
+            let length = read_u24(&handshake_data[1..4]);
+            if handshake_data.len() < 4 + length {
+                break;
+            }
+            let message: Vec<u8> = handshake_data.drain(..4 + length).collect();
+            let body = &message[4..];
+            match message[0] {
+                HANDSHAKE_SERVER_HELLO => server_hello = parse_server_hello(body),
+                HANDSHAKE_CERTIFICATE => {
+                    certificate = parse_certificate_message(body);
+                    finished = true;
+                }
+                HANDSHAKE_SERVER_HELLO_DONE => finished = true,
+                _ => {}
+            }
+        }
+
+        if finished {
+            break;
+        }
+    }
+
+    let (version, cipher) = server_hello.ok_or("no ServerHello received")?;
+    Ok(TlsInfo {
+        version: version_name(version),
+        cipher_suite: cipher_suite_name(cipher),
+        certificate,
+    })
+}
+
+fn read_record(stream: &mut TcpStream) -> Result<(u8, Vec<u8>), String> {
+    let mut header = [0u8; 5];
+    stream
+        .read_exact(&mut header)
+        .map_err(|_| String::from("no TLS response"))?;
+    if header[1] != 0x03 {
+        return Err(String::from("not a TLS service"));
+    }
+    let length = u16::from_be_bytes([header[3], header[4]]) as usize;
+    let mut payload = vec![0u8; length];
+    stream
+        .read_exact(&mut payload)
+        .map_err(|_| String::from("truncated TLS record"))?;
+    Ok((header[0], payload))
+}
+
+fn read_u24(bytes: &[u8]) -> usize {
+    ((bytes[0] as usize) << 16) | ((bytes[1] as usize) << 8) | bytes[2] as usize
+}
+
+fn parse_server_hello(body: &[u8]) -> Option<(u16, u16)> {
+    let version = u16::from_be_bytes([*body.first()?, *body.get(1)?]);
+    let session_id_len = *body.get(34)? as usize;
+    let offset = 35 + session_id_len;
+    let cipher = u16::from_be_bytes([*body.get(offset)?, *body.get(offset + 1)?]);
+    Some((version, cipher))
+}
+
+fn parse_certificate_message(body: &[u8]) -> Option<CertificateInfo> {
+    if body.len() < 6 {
+        return None;
+    }
+    let cert_len = read_u24(&body[3..6]);
+    let cert = body.get(6..6 + cert_len)?;
+    parse_certificate(cert)
+}
+
+fn push_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_extension(buf: &mut Vec<u8>, ext_type: u16, data: &[u8]) {
+    push_u16(buf, ext_type);
+    push_u16(buf, data.len() as u16);
+    buf.extend_from_slice(data);
+}
+
+fn client_random() -> [u8; 32] {
+    let mut seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0x5eed)
+        | 1;
+    let mut random = [0u8; 32];
+    for byte in random.iter_mut() {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        *byte = seed as u8;
+    }
+    random
+}
+
+/// Builds a ClientHello capped at TLS 1.2 so the certificate is sent in the clear.
+fn client_hello(server_name: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_u16(&mut body, 0x0303);
+    body.extend_from_slice(&client_random());
+    body.push(0); // no session id
+
+    push_u16(&mut body, (OFFERED_CIPHER_SUITES.len() * 2) as u16);
+    for suite in OFFERED_CIPHER_SUITES {
+        push_u16(&mut body, *suite);
+    }
+    body.extend_from_slice(&[1, 0]); // null compression only
+
+    let mut extensions = Vec::new();
+    let name = server_name.trim();
+    if !name.is_empty() && name.parse::<IpAddr>().is_err() {
+        let mut sni = Vec::new();
+        push_u16(&mut sni, (name.len() + 3) as u16);
+        sni.push(0);
+        push_u16(&mut sni, name.len() as u16);
+        sni.extend_from_slice(name.as_bytes());
+        push_extension(&mut extensions, 0x0000, &sni);
+    }
+    push_extension(
+        &mut extensions,
+        0x000a,
+        &[0x00, 0x08, 0x00, 0x1d, 0x00, 0x17, 0x00, 0x18, 0x00, 0x19],
+    );
+    push_extension(&mut extensions, 0x000b, &[0x01, 0x00]);
+    push_extension(
+        &mut extensions,
+        0x000d,
+        &[
+            0x00, 0x16, 0x04, 0x03, 0x05, 0x03, 0x06, 0x03, 0x08, 0x04, 0x08, 0x05, 0x08, 0x06,
+            0x04, 0x01, 0x05, 0x01, 0x06, 0x01, 0x02, 0x01, 0x02, 0x03,
+        ],
+    );
+    push_extension(&mut extensions, 0x0017, &[]);
+    push_extension(&mut extensions, 0xff01, &[0x00]);
+
+    push_u16(&mut body, extensions.len() as u16);
+    body.extend_from_slice(&extensions);
+
+    let mut handshake = vec![1u8];
+    handshake.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+    handshake.extend_from_slice(&body);
+
+    let mut record = vec![CONTENT_HANDSHAKE, 0x03, 0x01];
+    push_u16(&mut record, handshake.len() as u16);
+    record.extend_from_slice(&handshake);
+    record
+}
+
+fn version_name(version: u16) -> String {
+    match version {
+        0x0300 => String::from("SSLv3"),
+        0x0301 => String::from("TLSv1.0"),
+        0x0302 => String::from("TLSv1.1"),
+        0x0303 => String::from("TLSv1.2"),
+        0x0304 => String::from("TLSv1.3"),
+        other => format!("0x{:04X}", other),
+    }
+}
+
+fn cipher_suite_name(suite: u16) -> String {
+    let name = match suite {
+        0x0004 => "TLS_RSA_WITH_RC4_128_MD5",
+        0x0005 => "TLS_RSA_WITH_RC4_128_SHA",
+        0x000a => "TLS_RSA_WITH_3DES_EDE_CBC_SHA",
+        0x0016 => "TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA",
+        0x002f => "TLS_RSA_WITH_AES_128_CBC_SHA",
+        0x0033 => "TLS_DHE_RSA_WITH_AES_128_CBC_SHA",
+        0x0035 => "TLS_RSA_WITH_AES_256_CBC_SHA",
+        0x0039 => "TLS_DHE_RSA_WITH_AES_256_CBC_SHA",
+        0x003c => "TLS_RSA_WITH_AES_128_CBC_SHA256",
+        0x003d => "TLS_RSA_WITH_AES_256_CBC_SHA256",
+        0x009c => "TLS_RSA_WITH_AES_128_GCM_SHA256",
+        0x009d => "TLS_RSA_WITH_AES_256_GCM_SHA384",
+        0x009e => "TLS_DHE_RSA_WITH_AES_128_GCM_SHA256",
+        0x009f => "TLS_DHE_RSA_WITH_AES_256_GCM_SHA384",
+        0xc009 => "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA",
+        0xc00a => "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA",
+        0xc012 => "TLS_ECDHE_RSA_WITH_3DES_EDE_CBC_SHA",
+        0xc013 => "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA",
+        0xc014 => "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA",
+        0xc023 => "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA256",
+        0xc024 => "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA384",
+        0xc027 => "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256",
+        0xc028 => "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA384",
+        0xc02b => "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256",
+        0xc02c => "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384",
+        0xc02f => "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+        0xc030 => "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384",
+        0xcca8 => "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256",
+        0xcca9 => "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256",
+        other => return format!("0x{:04X}", other),
+    };
+    String::from(name)
+}
+
+fn alert_name(description: u8) -> String {
+    let name = match description {
+        40 => "handshake_failure",
+        47 => "illegal_parameter",
+        50 => "decode_error",
+        70 => "protocol_version",
+        71 => "insufficient_security",
+        80 => "internal_error",
+        112 => "unrecognized_name",
+        other => return format!("alert {}", other),
+    };
+    String::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    const LEAF: &[u8] = include_bytes!("../testdata/test-leaf.der");
+
+    fn handshake_message(kind: u8, body: &[u8]) -> Vec<u8> {
+        let mut message = vec![kind];
+        message.extend_from_slice(&(body.len() as u32).to_be_bytes()[1..]);
+        message.extend_from_slice(body);
+        message
+    }
+
+    fn record(content_type: u8, payload: &[u8]) -> Vec<u8> {
+        let mut record = vec![content_type, 0x03, 0x03];
+        push_u16(&mut record, payload.len() as u16);
+        record.extend_from_slice(payload);
+        record
+    }
+
+    /// ServerHello with the given version and suite, the test leaf
+    /// certificate and ServerHelloDone, in one handshake record.
+    fn server_flight(version: u16, cipher: u16) -> Vec<u8> {
+        let mut hello = version.to_be_bytes().to_vec();
+        hello.extend_from_slice(&[0x42; 32]);
+        hello.push(0); // no session id
+        push_u16(&mut hello, cipher);
+        hello.push(0); // null compression
+
+        let mut entry = (LEAF.len() as u32).to_be_bytes()[1..].to_vec();
+        entry.extend_from_slice(LEAF);
+        let mut certificates = (entry.len() as u32).to_be_bytes()[1..].to_vec();
+        certificates.extend_from_slice(&entry);
+
+        let mut payload = handshake_message(HANDSHAKE_SERVER_HELLO, &hello);
+        payload.extend(handshake_message(HANDSHAKE_CERTIFICATE, &certificates));
+        payload.extend(handshake_message(HANDSHAKE_SERVER_HELLO_DONE, &[]));
+        record(CONTENT_HANDSHAKE, &payload)
+    }
+
+    /// Accepts one connection, returns the ClientHello record it read and
+    /// answers with `reply`.
+    fn serve_once(reply: Vec<u8>) -> (SocketAddr, thread::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut header = [0u8; 5];
+            stream.read_exact(&mut header).unwrap();
+            let mut hello = vec![0u8; u16::from_be_bytes([header[3], header[4]]) as usize];
+            stream.read_exact(&mut hello).unwrap();
+            stream.write_all(&reply).unwrap();
+            hello
+        });
+        (addr, server)
+    }
+
+    #[test]
+    fn reads_certificate_from_handshake() {
+        let (addr, server) = serve_once(server_flight(0x0303, 0xc02f));
+        let info = inspect_tls(addr, "test.vonogs.local", Duration::from_secs(2)).unwrap();
+        let client_hello = server.join().unwrap();
+
+        assert_eq!(client_hello[0], 1, "first message is a ClientHello");
+        assert!(client_hello
+            .windows(b"test.vonogs.local".len())
+            .any(|w| w == b"test.vonogs.local"));
+        assert_eq!(info.version, "TLSv1.2");
+        assert_eq!(info.cipher_suite, "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256");
+        let cert = info.certificate.as_ref().expect("certificate parsed");
+        assert_eq!(cert.subject, "O=Vonogs Test, CN=test.vonogs.local");
+        assert!(!cert.self_signed);
+        // notAfter is 2035-06-01 12:00:00 UTC.
+        assert!(info.warnings_at(30, 1_893_456_000).is_empty());
+        assert_eq!(
+            info.warnings_at(30, 2_062_800_000),
+            vec!["EXPIRES IN 17 DAYS"]
+        );
+        assert_eq!(info.warnings_at(30, 2_064_312_001), vec!["EXPIRED"]);
+        assert!(info.weaknesses().is_empty());
+    }
+
+    #[test]
+    fn flags_weak_negotiation() {
+        let (addr, server) = serve_once(server_flight(0x0301, 0x0005));
+        let info = inspect_tls(addr, "test.vonogs.local", Duration::from_secs(2)).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(
+            info.weaknesses(),
+            ["protocol: TLSv1.0", "cipher: TLS_RSA_WITH_RC4_128_SHA"]
+        );
+    }
+
+    #[test]
+    fn no_sni_for_address_targets() {
+        let (addr, server) = serve_once(server_flight(0x0303, 0xc02f));
+        inspect_tls(addr, "127.0.0.1", Duration::from_secs(2)).unwrap();
+        let client_hello = server.join().unwrap();
+        assert!(!client_hello.windows(9).any(|w| w == b"127.0.0.1"));
+    }
+
+    #[test]
+    fn reports_alert() {
+        let (addr, server) = serve_once(record(CONTENT_ALERT, &[2, 40]));
+        let error = inspect_tls(addr, "test.vonogs.local", Duration::from_secs(2)).unwrap_err();
+        server.join().unwrap();
+        assert_eq!(error, "server sent alert: handshake_failure");
+    }
+
+    #[test]
+    fn rejects_plaintext_service() {
+        let (addr, server) = serve_once(b"HTTP/1.1 400 Bad Request\r\n\r\n".to_vec());
+        let error = inspect_tls(addr, "test.vonogs.local", Duration::from_secs(2)).unwrap_err();
+        server.join().unwrap();
+        assert_eq!(error, "not a TLS service");
+    }
+}
@@ -0,0 +1,27 @@
+use crate::color;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static LEVEL: AtomicU8 = AtomicU8::new(0);
+
+/// 0 by default, 1 for `-v`, 2 for `-vv`.
+pub fn set_level(level: u8) {
+    LEVEL.store(level.min(2), Ordering::Relaxed);
+}
+
+pub fn level() -> u8 {
+    LEVEL.load(Ordering::Relaxed)
+}
+
+/// Writes `message` to stderr when the verbosity is at least `level`, so
+/// quiet and grepable output on stdout stays clean. On a shared terminal the
+/// progress line is wiped first; the next tick draws it again.
+pub fn log(level: u8, message: &str) {
+    if self::level() < level {
+        return;
+    }
+    if color::redraw() && io::stderr().is_terminal() {
+        eprint!("\r\x1b[2K");
+    }
+    eprintln!("{}", message);
+}
@@ -0,0 +1,774 @@
+use crate::color::{
+    self, fit, stty, stty_size, BOLD, CYAN, DIM, GREEN, MAGENTA, RED, RESET, REVERSE, YELLOW,
+};
+use crate::engine::{abort_note, probe_port, HostWatch, PortResult, PortStatus};
+use crate::policy::parse_port_list;
+use crate::profiles::ScanProfile;
+use crate::progress::{clock, Progress};
+use crate::resolve::{resolve, Resolution};
+use crate::services::get_service_name;
+use crate::trace;
+use std::cell::{Cell, RefCell};
+use std::io::{self, IsTerminal, Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Hosts scanned at the same time; the rest wait in the queue.
+const MAX_WORKERS: usize = 8;
+const RECENT_RESULTS: usize = 200;
+
+/// The dashboard needs a real terminal on both ends and `stty` to switch it
+/// into raw mode. It is off in plain output mode and with `-v`, whose
+/// traces would run over the screen. `VONOGS_NO_TUI` forces the classic menu.
+pub fn is_available() -> bool {
+    !cfg!(target_os = "windows")
+        && color::redraw()
+        && trace::level() == 0
+        && io::stdin().is_terminal()
+        && io::stdout().is_terminal()
+        && std::env::var_os("VONOGS_NO_TUI").is_none()
+        && std::env::var("TERM").map(|t| t != "dumb").unwrap_or(false)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Key {
+    Char(char),
+    Enter,
+    Escape,
+    Backspace,
+    Up,
+    Down,
+    Interrupt,
+}
+
+/// Raw-mode, alternate-screen session. Dropping it restores the terminal.
+pub struct Terminal {
+    saved: String,
+    size: Cell<Option<(Instant, usize, usize)>>,
+    /// Bytes read but not yet turned into keys, e.g. pasted text.
+    pending: RefCell<Vec<u8>>,
+}
+
+impl Terminal {
+    pub fn enter() -> Result<Terminal, String> {
+        let saved = stty(&["-g"])?;
+        // Reads return after 100ms without input, so screens can refresh.
+        stty(&["-icanon", "-echo", "-isig", "min", "0", "time", "1"])?;
+        let mut stdout = io::stdout();
+        let _ = write!(stdout, "\x1b[?1049h\x1b[?25l\x1b[2J");
+        let _ = stdout.flush();
+        Ok(Terminal {
+            saved,
+            size: Cell::new(None),
+            pending: RefCell::new(Vec::new()),
+        })
+    }
+
+    /// Rows and columns, re-read from `stty` at most once a second.
+    fn size(&self) -> (usize, usize) {
+        if let Some((read_at, rows, cols)) = self.size.get() {
+            if read_at.elapsed() < Duration::from_secs(1) {
+                return (rows, cols);
+            }
+        }
+        let (rows, cols) = stty_size().unwrap_or((24, 80));
+        self.size.set(Some((Instant::now(), rows, cols)));
+        (rows, cols)
+    }
+
+    /// Waits up to 100ms for a key press.
+    fn read_key(&self) -> Option<Key> {
+        let mut pending = self.pending.borrow_mut();
+        if pending.is_empty() {
+            let mut buf = [0u8; 256];
+            let n = io::stdin().read(&mut buf).ok()?;
+            pending.extend_from_slice(&buf[..n]);
+        }
+        let (key, used) = parse_key(&pending)?;
+        pending.drain(..used);
+        key
+    }
+
+    /// Redraws the whole screen, cutting lines to the terminal size.
+    fn draw(&self, lines: &[String]) {
+        let (rows, cols) = self.size();
+        let mut frame = String::from("\x1b[H");
+        for (index, line) in lines.iter().take(rows).enumerate() {
+            if index > 0 {
+                frame.push_str("\r\n");
+            }
+            frame.push_str(&fit(line, cols));
+            frame.push_str(RESET);
+            frame.push_str("\x1b[K");
+        }
+        frame.push_str("\x1b[J");
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(frame.as_bytes());
+        let _ = stdout.flush();
+    }
+
+    /// Reads a line below `screen`; Escape or Ctrl+C gives `None`.
+    fn prompt(&self, screen: &[String], label: &str) -> Option<String> {
+        let mut input = String::new();
+        loop {
+            let mut lines = screen.to_vec();
+            lines.push(format!(" {}{}{}{} {}", BOLD, label, RESET, input, REVERSE) + " ");
+            self.draw(&lines);
+            match self.read_key() {
+                Some(Key::Enter) => return Some(input.trim().to_string()),
+                Some(Key::Escape) | Some(Key::Interrupt) => return None,
+                Some(Key::Backspace) => {
+                    input.pop();
+                }
+                Some(Key::Char(c)) if !c.is_control() => input.push(c),
+                _ => {}
+            }
+        }
+    }
+
+    fn rows(&self) -> usize {
+        self.size().0
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout();
+        let _ = write!(stdout, "{}\x1b[?25h\x1b[?1049l", RESET);
+        let _ = stdout.flush();
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+/// Decodes the first key in `bytes` and how many bytes it used. Escape
+/// sequences other than the arrow keys are consumed and ignored.
+fn parse_key(bytes: &[u8]) -> Option<(Option<Key>, usize)> {
+    let key = match bytes {
+        [] => return None,
+        [3, ..] => Key::Interrupt,
+        [b'\r', ..] | [b'\n', ..] => Key::Enter,
+        [0x7f, ..] | [8, ..] => Key::Backspace,
+        [0x1b, b'[' | b'O', rest @ ..] => {
+            let end = rest
+                .iter()
+                .position(|b| b.is_ascii_alphabetic() || *b == b'~');
+            let used = end.map_or(bytes.len(), |end| end + 3);
+            let key = match rest.get(end.unwrap_or(usize::MAX)) {
+                Some(b'A') => Some(Key::Up),
+                Some(b'B') => Some(Key::Down),
+                _ => None,
+            };
+            return Some((key, used));
+        }
+        [0x1b, ..] => Key::Escape,
+        [first, ..] => {
+            let len = match first.leading_ones() {
+                2..=4 => first.leading_ones() as usize,
+                _ => 1,
+            };
+            let len = len.min(bytes.len());
+            let c = String::from_utf8_lossy(&bytes[..len]).chars().next()?;
+            return Some((Some(Key::Char(c)), len));
+        }
+    };
+    Some((Some(key), 1))
+}
+
+fn rule() -> String {
+    format!("{}{}{}", DIM, "─".repeat(200), RESET)
+}
+
+fn bar(done: usize, total: usize, width: usize) -> String {
+    let filled = (width * done)
+        .checked_div(total)
+        .unwrap_or(width)
+        .min(width);
+    format!(
+        "[{}{}{}{}]",
+        GREEN,
+        "█".repeat(filled),
+        RESET,
+        "·".repeat(width - filled)
+    )
+}
+
+pub enum HomeAction {
+    Scan {
+        scan_type: String,
+        targets: Vec<String>,
+        ports: Vec<u16>,
+    },
+    ClassicMenu,
+    Quit,
+}
+
+/// Accepts a profile name or a port list such as `22,80,8000-8100`;
+/// empty input means the quick profile.
+fn parse_port_choice(input: &str) -> Result<(String, Vec<u16>), String> {
+    let profile = if input.trim().is_empty() {
+        Some(ScanProfile::Quick)
+    } else {
+        ScanProfile::from_name(input)
+    };
+    if let Some(profile) = profile {
+        return Ok((profile.get_name().to_string(), profile.get_ports()));
+    }
+    let mut ports = parse_port_list(input)?;
+    ports.sort_unstable();
+    ports.dedup();
+    if ports.is_empty() {
+        return Err(String::from("no ports given"));
+    }
+    Ok((String::from("Custom Port List"), ports))
+}
+
+/// The start screen that replaces the numbered menu. `messages` shows the
+/// outcome of the previous scan.
+pub fn home(terminal: &Terminal, title: &str, messages: &[String]) -> HomeAction {
+    let mut notice: Option<String> = None;
+    loop {
+        let mut screen = vec![
+            format!(" {}{}{}{}", CYAN, BOLD, title, RESET),
+            rule(),
+            format!(" {}[n]{} New scan", BOLD, RESET),
+            format!(
+                " {}[m]{} Classic menu {}(service, TLS and SSH inspection){}",
+                BOLD, RESET, DIM, RESET
+            ),
+            format!(" {}[q]{} Quit", BOLD, RESET),
+            rule(),
+        ];
+        screen.extend(messages.iter().map(|m| format!(" {}", m)));
+        if let Some(notice) = &notice {
+            screen.push(format!(" {}{}{}", YELLOW, notice, RESET));
+        }
+        terminal.draw(&screen);
+
+        match terminal.read_key() {
+            Some(Key::Char('n')) | Some(Key::Enter) => {
+                screen.push(String::new());
+                let targets = match terminal.prompt(&screen, "Targets (comma-separated):") {
+                    Some(input) => input,
+                    None => continue,
+                };
+                let mut targets: Vec<String> = targets
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|t| !t.is_empty())
+                    .map(String::from)
+                    .collect();
+                targets.dedup();
+                if targets.is_empty() {
+                    notice = Some(String::from("No targets given."));
+                    continue;
+                }
+
+                screen.push(format!(" Targets: {}{}{}", CYAN, targets.join(", "), RESET));
+                let choice = match terminal.prompt(
+                    &screen,
+                    "Ports (quick, web, database, full or 22,80,8000-8100) [quick]:",
+                ) {
+                    Some(input) => input,
+                    None => continue,
+                };
+                match parse_port_choice(&choice) {
+                    Ok((scan_type, ports)) => {
+                        return HomeAction::Scan {
+                            scan_type,
+                            targets,
+                            ports,
+                        }
+                    }
+                    Err(message) => notice = Some(format!("Invalid ports: {}", message)),
+                }
+            }
+            Some(Key::Char('m')) => return HomeAction::ClassicMenu,
+            Some(Key::Char('q')) | Some(Key::Interrupt) | Some(Key::Escape) => {
+                return HomeAction::Quit
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One host of a dashboard scan, filled in by the worker threads.
+#[derive(Debug, Clone)]
+pub struct HostScan {
+    pub target: String,
+    pub ports: Vec<u16>,
+    pub results: Vec<PortResult>,
+    pub error: Option<String>,
+    /// Where the target pointed when its scan started.
+    pub resolution: Option<Arc<Resolution>>,
+    /// Time spent probing; pauses are not counted.
+    pub elapsed_secs: f32,
+    started: bool,
+    pub finished: bool,
+}
+
+impl HostScan {
+    pub fn new(target: &str, ports: &[u16]) -> HostScan {
+        HostScan {
+            target: target.to_string(),
+            ports: ports.to_vec(),
+            results: Vec::new(),
+            error: None,
+            resolution: None,
+            elapsed_secs: 0.0,
+            started: false,
+            finished: false,
+        }
+    }
+
+    pub fn open_ports(&self) -> Vec<u16> {
+        self.results
+            .iter()
+            .filter(|r| r.status == PortStatus::Open)
+            .map(|r| r.port)
+            .collect()
+    }
+
+    /// True when the scan stopped before every port was probed.
+    pub fn is_incomplete(&self) -> bool {
+        self.error.is_none() && self.results.len() < self.ports.len()
+    }
+
+    fn count(&self, status: PortStatus) -> usize {
+        self.results.iter().filter(|r| r.status == status).count()
+    }
+
+    fn unreachable(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|r| r.status.is_host_failure())
+            .count()
+    }
+
+    fn remaining(&self) -> usize {
+        if self.finished || self.error.is_some() {
+            0
+        } else {
+            self.ports.len() - self.results.len()
+        }
+    }
+
+    fn eta(&self) -> Option<Duration> {
+        if self.results.is_empty() {
+            return None;
+        }
+        let secs = self.elapsed_secs / self.results.len() as f32 * self.remaining() as f32;
+        Some(Duration::from_secs_f32(secs))
+    }
+
+    fn state(&self) -> String {
+        match (&self.error, self.started, self.finished) {
+            (Some(error), _, _) => format!("{}{}{}", RED, error, RESET),
+            (None, false, _) => format!("{}queued{}", DIM, RESET),
+            (None, true, true) if self.is_incomplete() => format!("{}cancelled{}", YELLOW, RESET),
+            (None, true, true) => format!("{}done{}", GREEN, RESET),
+            (None, true, false) => match self.eta() {
+                Some(eta) => format!("ETA {}", clock(eta)),
+                None => String::from("starting"),
+            },
+        }
+    }
+}
+
+struct Dashboard {
+    hosts: Vec<HostScan>,
+    /// Open ports in the order they were found, as (host index, port).
+    open_feed: Vec<(usize, u16)>,
+    progress: Progress,
+}
+
+struct Control {
+    paused: AtomicBool,
+    cancel: AtomicBool,
+}
+
+fn worker(dashboard: Arc<Mutex<Dashboard>>, next: Arc<AtomicUsize>, control: Arc<Control>) {
+    loop {
+        let index = next.fetch_add(1, Ordering::SeqCst);
+        let (target, ports) = {
+            let mut dashboard = dashboard.lock().unwrap();
+            let host = match dashboard.hosts.get_mut(index) {
+                Some(host) => host,
+                None => return,
+            };
+            host.started = true;
+            (host.target.clone(), host.ports.clone())
+        };
+
+        let resolution = match resolve(&target) {
+            Ok(resolution) => resolution,
+            Err(_) => {
+                let mut dashboard = dashboard.lock().unwrap();
+                dashboard.hosts[index].error = Some(String::from("could not be resolved"));
+                dashboard.hosts[index].finished = true;
+                dashboard.progress.skip(ports.len());
+                continue;
+            }
+        };
+        dashboard.lock().unwrap().hosts[index].resolution = Some(Arc::clone(&resolution));
+
+        let mut watch = HostWatch::default();
+        for port in &ports {
+            let port = *port;
+            while control.paused.load(Ordering::Relaxed) && !control.cancel.load(Ordering::Relaxed)
+            {
+                thread::sleep(Duration::from_millis(100));
+            }
+            if control.cancel.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let probe_started = Instant::now();
+            let result = probe_port(&resolution, port);
+            let status = result.status;
+            let mut dashboard = dashboard.lock().unwrap();
+            let host = &mut dashboard.hosts[index];
+            host.elapsed_secs += probe_started.elapsed().as_secs_f32();
+            host.results.push(result);
+            dashboard.progress.record(status);
+            if status == PortStatus::Open {
+                dashboard.open_feed.push((index, port));
+            }
+            if watch.give_up(status) {
+                let host = &mut dashboard.hosts[index];
+                let scanned = host.results.len();
+                host.error = Some(abort_note(status, scanned, ports.len()));
+                dashboard.progress.skip(ports.len() - scanned);
+                break;
+            }
+        }
+        dashboard.lock().unwrap().hosts[index].finished = true;
+    }
+}
+
+#[derive(PartialEq)]
+enum View {
+    Overview,
+    Host,
+}
+
+fn render_overview(
+    dashboard: &Dashboard,
+    selected: usize,
+    header: &str,
+    rows: usize,
+) -> Vec<String> {
+    let hosts = &dashboard.hosts;
+    let progress = dashboard.progress.snapshot();
+    let errors = hosts.iter().filter(|h| h.error.is_some()).count() + progress.errors;
+    let eta = match progress.eta {
+        _ if hosts.iter().all(|h| h.finished) => String::from("—"),
+        Some(eta) => clock(eta),
+        None => String::from("…"),
+    };
+
+    let mut lines = vec![header.to_string(), rule()];
+
+    // Hosts pane: about a third of the screen, scrolled to the selection.
+    let host_rows = hosts.len().min(rows.saturating_sub(12).max(3) / 3).max(1);
+    let first = selected.saturating_sub(host_rows - 1);
+    lines.push(format!(
+        " {}HOSTS{}  {}{}/{}{}",
+        BOLD,
+        RESET,
+        DIM,
+        hosts.iter().filter(|h| h.finished).count(),
+        hosts.len(),
+        RESET
+    ));
+    for (index, host) in hosts.iter().enumerate().skip(first).take(host_rows) {
+        let marker = if index == selected {
+            format!("{}>{}", CYAN, RESET)
+        } else {
+            String::from(" ")
+        };
+        lines.push(format!(
+            " {} {:<24} {} {:>5}/{:<5} open {}{:<3}{} timeouts {:<4} {}",
+            marker,
+            host.target,
+            bar(host.results.len(), host.ports.len(), 20),
+            host.results.len(),
+            host.ports.len(),
+            GREEN,
+            host.count(PortStatus::Open),
+            RESET,
+            host.count(PortStatus::TimeoutFiltered),
+            host.state()
+        ));
+    }
+    lines.push(rule());
+
+    lines.push(format!(
+        " {}TOTAL{} {} {}/{} ports · {:.1} ports/s · ETA {}",
+        BOLD,
+        RESET,
+        bar(progress.done, progress.total, 20),
+        progress.done,
+        progress.total,
+        progress.rate,
+        eta
+    ));
+    lines.push(format!(
+        "       {}open {}{} · closed {} · {}timeouts {}{} · {}unreachable {}{} · {}errors {}{}",
+        GREEN,
+        progress.open,
+        RESET,
+        progress.closed,
+        YELLOW,
+        progress.filtered,
+        RESET,
+        MAGENTA,
+        progress.unreachable,
+        RESET,
+        RED,
+        errors,
+        RESET
+    ));
+    lines.push(rule());
+
+    lines.push(format!(
+        " {}OPEN PORTS{} {}(newest first){}",
+        BOLD, RESET, DIM, RESET
+    ));
+    lines.push(format!(
+        " {}{:<28} {:<7} {}{}",
+        DIM, "HOST", "PORT", "SERVICE", RESET
+    ));
+    let table_rows = rows.saturating_sub(lines.len() + 2);
+    if dashboard.open_feed.is_empty() {
+        lines.push(format!(" {}No open ports yet.{}", DIM, RESET));
+    }
+    for (index, port) in dashboard.open_feed.iter().rev().take(table_rows) {
+        lines.push(format!(
+            " {:<28} {}{:<7}{} {}{}{}",
+            hosts[*index].target,
+            YELLOW,
+            port,
+            RESET,
+            CYAN,
+            get_service_name(*port),
+            RESET
+        ));
+    }
+    lines
+}
+
+fn render_host(host: &HostScan, header: &str, rows: usize) -> Vec<String> {
+    let mut lines = vec![header.to_string(), rule()];
+    lines.push(format!(
+        " {}{}{}{} {} {}/{} ports · {}open {}{} · closed {} · {}timeouts {}{} · {}unreachable {}{} · {:.1}s · {}",
+        CYAN,
+        BOLD,
+        host.target,
+        RESET,
+        bar(host.results.len(), host.ports.len(), 20),
+        host.results.len(),
+        host.ports.len(),
+        GREEN,
+        host.count(PortStatus::Open),
+        RESET,
+        host.count(PortStatus::Closed),
+        YELLOW,
+        host.count(PortStatus::TimeoutFiltered),
+        RESET,
+        MAGENTA,
+        host.unreachable(),
+        RESET,
+        host.elapsed_secs,
+        host.state()
+    ));
+    lines.push(rule());
+
+    let open = host.open_ports();
+    lines.push(format!(" {}OPEN PORTS{}", BOLD, RESET));
+    if open.is_empty() {
+        lines.push(format!(" {}None found yet.{}", DIM, RESET));
+    }
+    let open_rows = rows.saturating_sub(10) / 2;
+    for port in open.iter().take(open_rows.max(1)) {
+        lines.push(format!(
+            "   {}{:<7}{} {}{}{}",
+            YELLOW,
+            port,
+            RESET,
+            CYAN,
+            get_service_name(*port),
+            RESET
+        ));
+    }
+    if open.len() > open_rows.max(1) {
+        lines.push(format!(
+            "   {}… {} more{}",
+            DIM,
+            open.len() - open_rows.max(1),
+            RESET
+        ));
+    }
+    lines.push(rule());
+
+    lines.push(format!(
+        " {}RECENT RESULTS{} {}(newest first){}",
+        BOLD, RESET, DIM, RESET
+    ));
+    let recent_rows = rows.saturating_sub(lines.len() + 2);
+    for result in host
+        .results
+        .iter()
+        .rev()
+        .take(recent_rows.min(RECENT_RESULTS))
+    {
+        let colour = match result.status {
+            PortStatus::Open => GREEN,
+            PortStatus::Closed => DIM,
+            PortStatus::TimeoutFiltered => YELLOW,
+            PortStatus::HostUnreachable | PortStatus::NetworkUnreachable => MAGENTA,
+            PortStatus::PermissionDenied
+            | PortStatus::ResourceExhausted
+            | PortStatus::InvalidAddress
+            | PortStatus::ProxyError => RED,
+        };
+        lines.push(format!(
+            "   {:<7} {:<16} {}{}{}",
+            result.port,
+            get_service_name(result.port),
+            colour,
+            result.describe(),
+            RESET
+        ));
+    }
+    lines
+}
+
+/// Runs a scan with live panes until the user leaves it, and returns every
+/// host with the results gathered, complete or not.
+pub fn run_dashboard(
+    terminal: &Terminal,
+    scan_type: &str,
+    targets: &[String],
+    ports: &[u16],
+) -> Vec<HostScan> {
+    let dashboard = Arc::new(Mutex::new(Dashboard {
+        hosts: targets.iter().map(|t| HostScan::new(t, ports)).collect(),
+        open_feed: Vec::new(),
+        progress: Progress::new(targets.len() * ports.len()),
+    }));
+    let control = Arc::new(Control {
+        paused: AtomicBool::new(false),
+        cancel: AtomicBool::new(false),
+    });
+    let next = Arc::new(AtomicUsize::new(0));
+    let workers: Vec<_> = (0..targets.len().min(MAX_WORKERS))
+        .map(|_| {
+            let dashboard = Arc::clone(&dashboard);
+            let next = Arc::clone(&next);
+            let control = Arc::clone(&control);
+            thread::spawn(move || worker(dashboard, next, control))
+        })
+        .collect();
+
+    let mut view = View::Overview;
+    let mut selected = 0;
+    let mut notice = String::new();
+
+    loop {
+        let done = workers.iter().all(|w| w.is_finished());
+        let (paused, elapsed) = {
+            let dashboard = dashboard.lock().unwrap();
+            if done {
+                dashboard.progress.stop();
+            }
+            (
+                dashboard.progress.is_paused(),
+                dashboard.progress.snapshot().elapsed,
+            )
+        };
+        let cancelled = control.cancel.load(Ordering::Relaxed);
+        let state = match (done, cancelled, paused) {
+            (true, true, _) => format!("{}{}CANCELLED{}", YELLOW, BOLD, RESET),
+            (true, false, _) => format!("{}{}DONE{}", GREEN, BOLD, RESET),
+            (false, true, _) => format!("{}{}CANCELLING…{}", YELLOW, BOLD, RESET),
+            (false, false, true) => format!("{}{}PAUSED{}", YELLOW, BOLD, RESET),
+            (false, false, false) => format!("{}{}RUNNING{}", CYAN, BOLD, RESET),
+        };
+        let header = format!(
+            " {}{}Vonogs Dashboard{} · {} · {} host(s) × {} port(s) · {} · {}",
+            CYAN,
+            BOLD,
+            RESET,
+            scan_type,
+            targets.len(),
+            ports.len(),
+            clock(elapsed),
+            state
+        );
+        let keys = match (&view, done) {
+            (View::Overview, false) => {
+                "[p] pause/resume  [c] cancel  [↑/↓] select host  [Enter] host details"
+            }
+            (View::Overview, true) => {
+                "[↑/↓] select host  [Enter] host details  [q] save logs and return"
+            }
+            (View::Host, false) => "[Esc] back  [p] pause/resume  [c] cancel",
+            (View::Host, true) => "[Esc] back  [q] save logs and return",
+        };
+
+        let rows = terminal.rows().max(12);
+        let mut lines = {
+            let dashboard = dashboard.lock().unwrap();
+            match view {
+                View::Overview => render_overview(&dashboard, selected, &header, rows - 2),
+                View::Host => render_host(&dashboard.hosts[selected], &header, rows - 2),
+            }
+        };
+        while lines.len() < rows - 2 {
+            lines.push(String::new());
+        }
+        lines.truncate(rows - 2);
+        lines.push(format!(" {}{}{}", YELLOW, notice, RESET));
+        lines.push(format!(" {}{}{}", REVERSE, keys, RESET));
+        terminal.draw(&lines);
+
+        let key = match terminal.read_key() {
+            Some(key) => key,
+            None => continue,
+        };
+        notice.clear();
+        match key {
+            Key::Char('p') | Key::Char(' ') if !done && !cancelled => {
+                let dashboard = dashboard.lock().unwrap();
+                if paused {
+                    dashboard.progress.resume();
+                } else {
+                    dashboard.progress.pause();
+                }
+                control.paused.store(!paused, Ordering::Relaxed);
+            }
+            Key::Char('c') | Key::Interrupt if !done && !cancelled => {
+                control.cancel.store(true, Ordering::Relaxed);
+                notice = String::from("Cancelling; waiting for probes in flight…");
+            }
+            Key::Char('q') | Key::Interrupt if done => break,
+            Key::Char('q') => {
+                notice = String::from("The scan is still running; press c to cancel it first.")
+            }
+            Key::Up | Key::Char('k') => selected = selected.saturating_sub(1),
+            Key::Down | Key::Char('j') => selected = (selected + 1).min(targets.len() - 1),
+            Key::Enter if view == View::Overview => view = View::Host,
+            Key::Escape | Key::Backspace | Key::Enter if view == View::Host => {
+                view = View::Overview
+            }
+            _ => {}
+        }
+    }
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+    let dashboard = dashboard.lock().unwrap();
+    dashboard.hosts.clone()
+}
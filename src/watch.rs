@@ -0,0 +1,307 @@
+use crate::engine::{PortResult, PortStatus};
+use crate::http_probe::inspect_http;
+use crate::json;
+use crate::policy::parse_ports;
+use crate::profiles::ScanProfile;
+use crate::services::{get_service_name, is_tls_port};
+use crate::ssh::{inspect_ssh, is_ssh_service};
+use crate::tls::inspect_tls;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+
+/// A host to rescan, from a `[host <target>]` section.
+#[derive(Debug, Clone)]
+pub struct WatchTarget {
+    pub target: String,
+    pub profile: ScanProfile,
+    pub extra_ports: Vec<u16>,
+}
+
+impl WatchTarget {
+    pub fn ports_to_scan(&self) -> Vec<u16> {
+        let mut ports = self.profile.get_ports();
+        ports.extend(&self.extra_ports);
+        ports.sort_unstable();
+        ports.dedup();
+        ports
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    pub interval: Duration,
+    pub fingerprint: bool,
+    pub log_path: String,
+    pub log_max_bytes: u64,
+    pub log_keep: usize,
+    pub events_file: Option<String>,
+    pub metrics_listen: Option<String>,
+    pub targets: Vec<WatchTarget>,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        WatchConfig {
+            interval: Duration::from_secs(300),
+            fingerprint: true,
+            log_path: String::from("scan_logs/watch.log"),
+            log_max_bytes: 1024 * 1024,
+            log_keep: 5,
+            events_file: None,
+            metrics_listen: None,
+            targets: Vec::new(),
+        }
+    }
+}
+
+fn parse_number(key: &str, value: &str, line_number: usize) -> Result<u64, String> {
+    match value.parse::<u64>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(format!(
+            "line {}: {} must be a positive number",
+            line_number, key
+        )),
+    }
+}
+
+/// Parses global settings followed by `[host <target>]` sections with
+/// `profile` and `ports` keys. Any error makes the whole config invalid.
+pub fn parse_watch_config(contents: &str) -> Result<WatchConfig, String> {
+    let mut config = WatchConfig::default();
+
+    for (index, raw_line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let line = raw_line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            match header.trim().strip_prefix("host ") {
+                Some(target) if !target.trim().is_empty() => config.targets.push(WatchTarget {
+                    target: target.trim().to_string(),
+                    profile: ScanProfile::Quick,
+                    extra_ports: Vec::new(),
+                }),
+                _ => return Err(format!("line {}: expected [host <target>]", line_number)),
+            }
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .map(|(k, v)| (k.trim(), v.trim()))
+            .ok_or_else(|| format!("line {}: expected key = value", line_number))?;
+
+        match (config.targets.last_mut(), key) {
+            (Some(host), "profile") => {
+                host.profile = ScanProfile::from_name(value)
+                    .ok_or_else(|| format!("line {}: unknown profile '{}'", line_number, value))?
+            }
+            (Some(host), "ports") => host.extra_ports.extend(parse_ports(value, line_number)?),
+            (None, "interval") => {
+                config.interval = Duration::from_secs(parse_number(key, value, line_number)?)
+            }
+            (None, "fingerprint") => config.fingerprint = value != "false" && value != "no",
+            (None, "log_file") => config.log_path = value.to_string(),
+            (None, "log_max_kb") => {
+                config.log_max_bytes = parse_number(key, value, line_number)? * 1024
+            }
+            (None, "log_keep") => config.log_keep = parse_number(key, value, line_number)? as usize,
+            (None, "events_file") => config.events_file = Some(value.to_string()),
+            (None, "metrics_listen") => config.metrics_listen = Some(value.to_string()),
+            (_, other) => return Err(format!("line {}: unknown key '{}'", line_number, other)),
+        }
+    }
+
+    if config.targets.is_empty() {
+        return Err(String::from("no [host <target>] sections found"));
+    }
+    Ok(config)
+}
+
+/// Open ports of one host, each with an optional service fingerprint.
+pub type HostState = BTreeMap<u16, Option<String>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeKind {
+    Opened,
+    Closed,
+    ServiceChanged { before: String, after: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct ChangeEvent {
+    pub target: String,
+    pub port: u16,
+    pub kind: ChangeKind,
+    pub fingerprint: Option<String>,
+}
+
+impl ChangeEvent {
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            ChangeKind::Opened => "port_opened",
+            ChangeKind::Closed => "port_closed",
+            ChangeKind::ServiceChanged { .. } => "service_changed",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        let service = get_service_name(self.port);
+        match &self.kind {
+            ChangeKind::Opened => match &self.fingerprint {
+                Some(fingerprint) => {
+                    format!("port {} {} opened ({})", self.port, service, fingerprint)
+                }
+                None => format!("port {} {} opened", self.port, service),
+            },
+            ChangeKind::Closed => format!("port {} {} closed", self.port, service),
+            ChangeKind::ServiceChanged { before, after } => format!(
+                "port {} {} service changed: '{}' -> '{}'",
+                self.port, service, before, after
+            ),
+        }
+    }
+
+    pub fn to_json(&self, timestamp: &str) -> String {
+        format!(
+            "{{\"time\":{},\"target\":{},\"event\":{},\"port\":{},\"service\":{},\"message\":{}}}",
+            json::string(timestamp),
+            json::string(&self.target),
+            json::string(self.name()),
+            self.port,
+            json::string(get_service_name(self.port)),
+            json::string(&self.message())
+        )
+    }
+}
+
+pub fn open_ports(results: &[PortResult]) -> Vec<u16> {
+    results
+        .iter()
+        .filter(|r| r.status == PortStatus::Open)
+        .map(|r| r.port)
+        .collect()
+}
+
+/// Compares the previous and current state of a host. Fingerprints are only
+/// compared when both scans produced one, so a probe that times out once does
+/// not look like a service change.
+pub fn diff_states(target: &str, previous: &HostState, current: &HostState) -> Vec<ChangeEvent> {
+    let mut events = Vec::new();
+    let event = |port: u16, kind: ChangeKind, fingerprint: &Option<String>| ChangeEvent {
+        target: target.to_string(),
+        port,
+        kind,
+        fingerprint: fingerprint.clone(),
+    };
+
+    for (port, fingerprint) in current {
+        match previous.get(port) {
+            None => events.push(event(*port, ChangeKind::Opened, fingerprint)),
+            Some(before) => {
+                if let (Some(before), Some(after)) = (before, fingerprint) {
+                    if before != after {
+                        let kind = ChangeKind::ServiceChanged {
+                            before: before.clone(),
+                            after: after.clone(),
+                        };
+                        events.push(event(*port, kind, fingerprint));
+                    }
+                }
+            }
+        }
+    }
+    for (port, fingerprint) in previous {
+        if !current.contains_key(port) {
+            events.push(event(*port, ChangeKind::Closed, fingerprint));
+        }
+    }
+
+    events.sort_by_key(|e| e.port);
+    events
+}
+
+/// A short, stable description of what answers on a port: the SSH
+/// identification, the HTTP `Server` header or the TLS certificate subject.
+pub fn fingerprint(addr: SocketAddr, target: &str, timeout: Duration) -> Option<String> {
+    let port = addr.port();
+    let service = get_service_name(port);
+
+    if is_ssh_service(service) {
+        inspect_ssh(addr, target, timeout)
+            .ok()
+            .map(|info| info.identification)
+    } else if is_tls_port(port) {
+        let info = inspect_tls(addr, target, timeout).ok()?;
+        let certificate = info.certificate?;
+        Some(format!(
+            "{} until {}",
+            certificate.subject, certificate.not_after
+        ))
+    } else if service.starts_with("HTTP") {
+        let info = inspect_http(addr, target, timeout)?;
+        Some(
+            info.server
+                .unwrap_or_else(|| format!("HTTP {}", info.status_code)),
+        )
+    } else {
+        None
+    }
+}
+
+/// Append-only log that rolls over to `<path>.1` .. `<path>.<keep>` once it
+/// grows past `max_bytes`.
+pub struct RotatingLog {
+    path: String,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingLog {
+    pub fn new(path: &str, max_bytes: u64, keep: usize) -> RotatingLog {
+        RotatingLog {
+            path: path.to_string(),
+            max_bytes,
+            keep,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn write_line(&self, line: &str) -> Result<(), String> {
+        let size = fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if size > 0 && size + line.len() as u64 + 1 > self.max_bytes {
+            self.rotate();
+        }
+        append_line(&self.path, line)
+    }
+
+    fn rotate(&self) {
+        let _ = fs::remove_file(format!("{}.{}", self.path, self.keep));
+        for index in (1..self.keep).rev() {
+            let from = format!("{}.{}", self.path, index);
+            if Path::new(&from).exists() {
+                let _ = fs::rename(&from, format!("{}.{}", self.path, index + 1));
+            }
+        }
+        let _ = fs::rename(&self.path, format!("{}.1", self.path));
+    }
+}
+
+pub fn append_line(path: &str, line: &str) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("could not open {}: {}", path, e))?;
+    file.write_all(format!("{}\n", line).as_bytes())
+        .map_err(|e| format!("could not write {}: {}", path, e))
+}
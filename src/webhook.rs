@@ -0,0 +1,436 @@
+use crate::engine::resolve_addr;
+use crate::findings::{Finding, Severity};
+use crate::json;
+use crate::services::get_service_name;
+use crate::sha256::{hmac_sha256, to_hex};
+use crate::watch::ChangeEvent;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+pub const SIGNATURE_HEADER: &str = "X-Vonogs-Signature";
+
+/// Where and how to deliver notifications, read from `VONOGS_WEBHOOK_*`.
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub url: String,
+    host: String,
+    port: u16,
+    path: String,
+    pub secret: Option<String>,
+    pub min_severity: Severity,
+    pub retries: u32,
+    pub backoff: Duration,
+    pub timeout: Duration,
+}
+
+fn env_number(name: &str, default: u64) -> Result<u64, String> {
+    match std::env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| format!("{} must be a number", name)),
+        Err(_) => Ok(default),
+    }
+}
+
+/// `None` when no webhook URL is configured.
+pub fn config_from_env() -> Result<Option<WebhookConfig>, String> {
+    let url = match std::env::var("VONOGS_WEBHOOK_URL") {
+        Ok(url) if !url.trim().is_empty() => url.trim().to_string(),
+        _ => return Ok(None),
+    };
+    let (host, port, path) = parse_url(&url)?;
+
+    let min_severity = match std::env::var("VONOGS_WEBHOOK_MIN_SEVERITY") {
+        Ok(value) => Severity::parse(&value)
+            .ok_or_else(|| format!("unknown VONOGS_WEBHOOK_MIN_SEVERITY '{}'", value))?,
+        Err(_) => Severity::High,
+    };
+
+    Ok(Some(WebhookConfig {
+        url,
+        host,
+        port,
+        path,
+        secret: std::env::var("VONOGS_WEBHOOK_SECRET")
+            .ok()
+            .filter(|s| !s.is_empty()),
+        min_severity,
+        retries: env_number("VONOGS_WEBHOOK_RETRIES", 3)? as u32,
+        backoff: Duration::from_millis(env_number("VONOGS_WEBHOOK_BACKOFF_MS", 1000)?),
+        timeout: Duration::from_millis(env_number("VONOGS_WEBHOOK_TIMEOUT_MS", 5000)?),
+    }))
+}
+
+/// Splits `http://host[:port][/path]` into its parts.
+fn parse_url(url: &str) -> Result<(String, u16, String), String> {
+    let rest =
+        match url.strip_prefix("http://") {
+            Some(rest) => rest,
+            None if url.starts_with("https://") => return Err(String::from(
+                "only http:// webhook URLs are supported; use a local relay for HTTPS endpoints",
+            )),
+            None => return Err(format!("invalid webhook URL '{}'", url)),
+        };
+
+    let (authority, path) = match rest.find('/') {
+        Some(idx) => (&rest[..idx], &rest[idx..]),
+        None => (rest, "/"),
+    };
+    // IPv6 literals are bracketed: http://[::1]:8080/hook
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => match bracketed.split_once(']') {
+            Some((host, rest)) => (host, rest.strip_prefix(':')),
+            None => return Err(format!("invalid webhook URL '{}'", url)),
+        },
+        None => match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(port) => port
+            .parse::<u16>()
+            .map_err(|_| format!("invalid port in webhook URL '{}'", url))?,
+        None => 80,
+    };
+    if host.is_empty() {
+        return Err(format!("missing host in webhook URL '{}'", url));
+    }
+
+    Ok((host.to_string(), port, path.to_string()))
+}
+
+pub fn signature(secret: &str, body: &str) -> String {
+    format!(
+        "sha256={}",
+        to_hex(&hmac_sha256(secret.as_bytes(), body.as_bytes()))
+    )
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Delivery {
+    pub status_code: u16,
+    pub attempts: u32,
+}
+
+enum AttemptError {
+    Retryable(String),
+    Fatal(String),
+}
+
+/// POSTs `body` and retries connection failures, `429` and `5xx` replies
+/// with exponential backoff. Other `4xx` replies are not retried.
+pub fn send(config: &WebhookConfig, event: &str, body: &str) -> Result<Delivery, String> {
+    let mut delay = config.backoff;
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match post(config, event, body) {
+            Ok(status_code) => {
+                return Ok(Delivery {
+                    status_code,
+                    attempts,
+                })
+            }
+            Err(AttemptError::Fatal(message)) => return Err(message),
+            Err(AttemptError::Retryable(message)) => {
+                if attempts > config.retries {
+                    return Err(format!("{} (after {} attempts)", message, attempts));
+                }
+            }
+        }
+        thread::sleep(delay);
+        delay = delay.saturating_mul(2);
+    }
+}
+
+fn post(config: &WebhookConfig, event: &str, body: &str) -> Result<u16, AttemptError> {
+    let addr = resolve_addr(&config.host, config.port)
+        .ok_or_else(|| AttemptError::Retryable(format!("could not resolve {}", config.host)))?;
+    let mut stream = TcpStream::connect_timeout(&addr, config.timeout)
+        .map_err(|e| AttemptError::Retryable(format!("connect failed: {}", e)))?;
+    let _ = stream.set_read_timeout(Some(config.timeout));
+    let _ = stream.set_write_timeout(Some(config.timeout));
+
+    let host = if config.host.contains(':') {
+        format!("[{}]", config.host)
+    } else {
+        config.host.clone()
+    };
+    let host_header = match config.port {
+        80 => host,
+        port => format!("{}:{}", host, port),
+    };
+    let mut request = format!(
+        "POST {} HTTP/1.1\r\n\
+         Host: {}\r\n\
+         User-Agent: vonogs-sentinel\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         X-Vonogs-Event: {}\r\n",
+        config.path,
+        host_header,
+        body.len(),
+        event
+    );
+    if let Some(secret) = &config.secret {
+        request.push_str(&format!(
+            "{}: {}\r\n",
+            SIGNATURE_HEADER,
+            signature(secret, body)
+        ));
+    }
+    request.push_str("Connection: close\r\n\r\n");
+    request.push_str(body);
+
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| AttemptError::Retryable(format!("write failed: {}", e)))?;
+
+    let mut response = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !response.windows(2).any(|w| w == b"\r\n") {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => response.extend_from_slice(&buf[..n]),
+            Err(_) => break,
+        }
+    }
+
+    let status_code = String::from_utf8_lossy(&response)
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| AttemptError::Retryable(String::from("no HTTP response")))?;
+    match status_code {
+        200..=299 => Ok(status_code),
+        429 | 500..=599 => Err(AttemptError::Retryable(format!("HTTP {}", status_code))),
+        other => Err(AttemptError::Fatal(format!("HTTP {}", other))),
+    }
+}
+
+fn open_ports_json(open_ports: &[u16]) -> String {
+    let ports: Vec<String> = open_ports
+        .iter()
+        .map(|port| {
+            format!(
+                "{{\"port\":{},\"service\":{}}}",
+                port,
+                json::string(get_service_name(*port))
+            )
+        })
+        .collect();
+    format!("[{}]", ports.join(","))
+}
+
+fn findings_json(findings: &[Finding]) -> String {
+    let findings: Vec<String> = findings.iter().map(Finding::to_json).collect();
+    format!("[{}]", findings.join(","))
+}
+
+/// Payload for `scan_completed` and `findings_detected` events.
+pub fn scan_payload(
+    event: &str,
+    timestamp: &str,
+    scan_type: &str,
+    target: &str,
+    open_ports: &[u16],
+    findings: &[Finding],
+) -> String {
+    format!(
+        "{{\"event\":{},\"timestamp\":{},\"scan_type\":{},\"target\":{},\"open_ports\":{},\"findings\":{}}}",
+        json::string(event),
+        json::string(timestamp),
+        json::string(scan_type),
+        json::string(target),
+        open_ports_json(open_ports),
+        findings_json(findings)
+    )
+}
+
+/// Payload for `watch_change` events.
+pub fn change_payload(
+    timestamp: &str,
+    target: &str,
+    open_ports: &[u16],
+    changes: &[ChangeEvent],
+    findings: &[Finding],
+) -> String {
+    let changes: Vec<String> = changes.iter().map(|c| c.to_json(timestamp)).collect();
+    format!(
+        "{{\"event\":\"watch_change\",\"timestamp\":{},\"target\":{},\"open_ports\":{},\"changes\":[{}],\"findings\":{}}}",
+        json::string(timestamp),
+        json::string(target),
+        open_ports_json(open_ports),
+        changes.join(","),
+        findings_json(findings)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    #[test]
+    fn parses_urls() {
+        assert_eq!(
+            parse_url("http://hooks.example.com/vonogs").unwrap(),
+            (
+                String::from("hooks.example.com"),
+                80,
+                String::from("/vonogs")
+            )
+        );
+        assert_eq!(
+            parse_url("http://10.0.0.5:8080").unwrap(),
+            (String::from("10.0.0.5"), 8080, String::from("/"))
+        );
+        assert_eq!(
+            parse_url("http://[::1]:9000/a/b?x=1").unwrap(),
+            (String::from("::1"), 9000, String::from("/a/b?x=1"))
+        );
+        assert_eq!(
+            parse_url("http://[fd00::5]/hook").unwrap(),
+            (String::from("fd00::5"), 80, String::from("/hook"))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_urls() {
+        assert!(parse_url("https://hooks.example.com/")
+            .unwrap_err()
+            .starts_with("only http://"));
+        assert!(parse_url("ftp://example.com/").is_err());
+        assert!(parse_url("http://:8080/hook").is_err());
+        assert!(parse_url("http://example.com:99999/").is_err());
+        assert!(parse_url("http://example.com:port/").is_err());
+        assert!(parse_url("http://[::1/hook").is_err());
+    }
+
+    #[test]
+    fn signs_body() {
+        assert_eq!(
+            signature("Jefe", "what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    fn config(port: u16, retries: u32) -> WebhookConfig {
+        WebhookConfig {
+            url: format!("http://127.0.0.1:{}/hook", port),
+            host: String::from("127.0.0.1"),
+            port,
+            path: String::from("/hook"),
+            secret: Some(String::from("s3cret")),
+            min_severity: Severity::High,
+            retries,
+            backoff: Duration::from_millis(40),
+            timeout: Duration::from_secs(2),
+        }
+    }
+
+    /// Answers one connection per status in `statuses`, in order, and
+    /// returns every request it read.
+    fn serve(statuses: Vec<u16>) -> (u16, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                        let length = head
+                            .lines()
+                            .find_map(|l| l.strip_prefix("Content-Length: "))
+                            .and_then(|l| l.parse::<usize>().ok())
+                            .unwrap_or(0);
+                        if body.len() >= length {
+                            break;
+                        }
+                    }
+                    if n == 0 {
+                        break;
+                    }
+                }
+                let reply = format!("HTTP/1.1 {} Test\r\nContent-Length: 0\r\n\r\n", status);
+                stream.write_all(reply.as_bytes()).unwrap();
+                requests.push(String::from_utf8_lossy(&request).into_owned());
+            }
+            requests
+        });
+        (port, server)
+    }
+
+    #[test]
+    fn posts_signed_event() {
+        let (port, server) = serve(vec![204]);
+        let body = "{\"event\":\"test\"}";
+        let delivery = send(&config(port, 0), "test", body).unwrap();
+        let requests = server.join().unwrap();
+
+        assert_eq!(delivery.status_code, 204);
+        assert_eq!(delivery.attempts, 1);
+        let request = &requests[0];
+        assert!(request.starts_with("POST /hook HTTP/1.1\r\n"));
+        assert!(request.contains(&format!("Host: 127.0.0.1:{}\r\n", port)));
+        assert!(request.contains("X-Vonogs-Event: test\r\n"));
+        assert!(request.contains(&format!(
+            "{}: {}\r\n",
+            SIGNATURE_HEADER,
+            signature("s3cret", body)
+        )));
+        assert!(request.ends_with(body));
+    }
+
+    #[test]
+    fn retries_server_errors_with_backoff() {
+        let (port, server) = serve(vec![503, 429, 200]);
+        let started = Instant::now();
+        let delivery = send(&config(port, 3), "test", "{}").unwrap();
+        let elapsed = started.elapsed();
+        assert_eq!(server.join().unwrap().len(), 3);
+
+        assert_eq!(delivery.status_code, 200);
+        assert_eq!(delivery.attempts, 3);
+        // 40 ms, then doubled to 80 ms.
+        assert!(elapsed >= Duration::from_millis(120), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn gives_up_after_retries() {
+        let (port, server) = serve(vec![500, 502, 500]);
+        let error = send(&config(port, 2), "test", "{}").unwrap_err();
+        assert_eq!(server.join().unwrap().len(), 3);
+        assert_eq!(error, "HTTP 500 (after 3 attempts)");
+    }
+
+    #[test]
+    fn client_errors_are_fatal() {
+        let (port, server) = serve(vec![404]);
+        let error = send(&config(port, 3), "test", "{}").unwrap_err();
+        assert_eq!(server.join().unwrap().len(), 1);
+        assert_eq!(error, "HTTP 404");
+    }
+
+    #[test]
+    fn retries_refused_connections() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let error = send(&config(port, 1), "test", "{}").unwrap_err();
+        assert!(error.starts_with("connect failed"), "{}", error);
+        assert!(error.ends_with("(after 2 attempts)"), "{}", error);
+    }
+}
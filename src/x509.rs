@@ -0,0 +1,312 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_OID: u8 = 0x06;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_VERSION: u8 = 0xa0;
+const TAG_EXTENSIONS: u8 = 0xa3;
+const TAG_SAN_DNS: u8 = 0x82;
+const TAG_SAN_IP: u8 = 0x87;
+
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+
+#[derive(Debug, Clone)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub sans: Vec<String>,
+    pub not_after: String,
+    pub not_after_unix: i64,
+    pub self_signed: bool,
+}
+
+impl CertificateInfo {
+    pub fn days_until_expiry(&self) -> i64 {
+        self.days_until_expiry_at(now_unix())
+    }
+
+    /// Whole days from `now`, in Unix seconds, to `notAfter`; negative once
+    /// the certificate has expired.
+    pub fn days_until_expiry_at(&self, now: i64) -> i64 {
+        (self.not_after_unix - now).div_euclid(86_400)
+    }
+}
+
+pub fn now_unix() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// A reader over DER, or BER with definite lengths as LDAP sends it.
+pub struct Der<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Der<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Der { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    /// Reads the next TLV, returning its tag, contents and full encoding.
+    pub fn next(&mut self) -> Option<(u8, &'a [u8], &'a [u8])> {
+        let tag = *self.data.first()?;
+        let first_len = *self.data.get(1)?;
+        let (len, header) = if first_len & 0x80 == 0 {
+            (first_len as usize, 2)
+        } else {
+            let count = (first_len & 0x7f) as usize;
+            if count == 0 || count > 4 {
+                return None;
+            }
+            let mut len = 0usize;
+            for i in 0..count {
+                len = (len << 8) | *self.data.get(2 + i)? as usize;
+            }
+            (len, 2 + count)
+        };
+
+        let end = header.checked_add(len)?;
+        if end > self.data.len() {
+            return None;
+        }
+        let raw = &self.data[..end];
+        let contents = &self.data[header..end];
+        self.data = &self.data[end..];
+        Some((tag, contents, raw))
+    }
+
+    pub fn expect(&mut self, tag: u8) -> Option<&'a [u8]> {
+        match self.next()? {
+            (t, contents, _) if t == tag => Some(contents),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse_certificate(der: &[u8]) -> Option<CertificateInfo> {
+    let cert = Der::new(der).expect(TAG_SEQUENCE)?;
+    let tbs = Der::new(cert).expect(TAG_SEQUENCE)?;
+    let mut fields = Der::new(tbs);
+
+    if fields.peek_tag() == Some(TAG_VERSION) {
+        fields.next()?;
+    }
+    fields.next()?; // serialNumber
+    fields.next()?; // signature algorithm
+    let (_, issuer, issuer_raw) = fields.next()?;
+    let validity = fields.expect(TAG_SEQUENCE)?;
+    let (_, subject, subject_raw) = fields.next()?;
+    fields.next()?; // subjectPublicKeyInfo
+
+    let mut sans = Vec::new();
+    while let Some((tag, contents, _)) = fields.next() {
+        if tag == TAG_EXTENSIONS {
+            sans = parse_subject_alt_names(contents).unwrap_or_default();
+        }
+    }
+
+    let mut validity = Der::new(validity);
+    validity.next()?; // notBefore
+    let (time_tag, not_after, _) = validity.next()?;
+    let (not_after_unix, not_after) = parse_time(time_tag, not_after)?;
+
+    Some(CertificateInfo {
+        subject: format_name(subject),
+        issuer: format_name(issuer),
+        sans,
+        not_after,
+        not_after_unix,
+        self_signed: issuer_raw == subject_raw,
+    })
+}
+
+fn attribute_label(oid: &[u8]) -> Option<&'static str> {
+    match oid {
+        [0x55, 0x04, 0x03] => Some("CN"),
+        [0x55, 0x04, 0x06] => Some("C"),
+        [0x55, 0x04, 0x07] => Some("L"),
+        [0x55, 0x04, 0x08] => Some("ST"),
+        [0x55, 0x04, 0x0a] => Some("O"),
+        [0x55, 0x04, 0x0b] => Some("OU"),
+        _ => None,
+    }
+}
+
+fn format_name(name: &[u8]) -> String {
+    let mut parts = Vec::new();
+    let mut rdns = Der::new(name);
+    while let Some((tag, rdn, _)) = rdns.next() {
+        if tag != TAG_SET {
+            continue;
+        }
+        let mut attributes = Der::new(rdn);
+        while let Some(attribute) = attributes.expect(TAG_SEQUENCE) {
+            let mut attribute = Der::new(attribute);
+            let oid = match attribute.expect(TAG_OID) {
+                Some(oid) => oid,
+                None => continue,
+            };
+            let value = match attribute.next() {
+                Some((_, value, _)) => String::from_utf8_lossy(value).to_string(),
+                None => continue,
+            };
+            if let Some(label) = attribute_label(oid) {
+                parts.push(format!("{}={}", label, value));
+            }
+        }
+    }
+    parts.join(", ")
+}
+
+fn parse_subject_alt_names(extensions: &[u8]) -> Option<Vec<String>> {
+    let list = Der::new(extensions).expect(TAG_SEQUENCE)?;
+    let mut list = Der::new(list);
+
+    while !list.is_empty() {
+        let mut extension = Der::new(list.expect(TAG_SEQUENCE)?);
+        let oid = extension.expect(TAG_OID)?;
+        if extension.peek_tag() == Some(TAG_BOOLEAN) {
+            extension.next()?;
+        }
+        let value = extension.expect(TAG_OCTET_STRING)?;
+        if oid != OID_SUBJECT_ALT_NAME {
+            continue;
+        }
+
+        let names = Der::new(value).expect(TAG_SEQUENCE)?;
+        let mut names = Der::new(names);
+        let mut sans = Vec::new();
+        while let Some((tag, contents, _)) = names.next() {
+            match tag {
+                TAG_SAN_DNS => sans.push(String::from_utf8_lossy(contents).to_string()),
+                TAG_SAN_IP => {
+                    if let Some(ip) = format_ip(contents) {
+                        sans.push(ip);
+                    }
+                }
+                _ => {}
+            }
+        }
+        return Some(sans);
+    }
+
+    Some(Vec::new())
+}
+
+fn format_ip(bytes: &[u8]) -> Option<String> {
+    match bytes.len() {
+        4 => {
+            let octets: [u8; 4] = bytes.try_into().ok()?;
+            Some(std::net::Ipv4Addr::from(octets).to_string())
+        }
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            Some(std::net::Ipv6Addr::from(octets).to_string())
+        }
+        _ => None,
+    }
+}
+
+fn parse_digits(text: &str, range: std::ops::Range<usize>) -> Option<i64> {
+    text.get(range)?.parse::<i64>().ok()
+}
+
+fn parse_time(tag: u8, contents: &[u8]) -> Option<(i64, String)> {
+    let text = std::str::from_utf8(contents).ok()?;
+    let (year, rest) = match tag {
+        TAG_UTC_TIME => {
+            let yy = parse_digits(text, 0..2)?;
+            (if yy >= 50 { 1900 + yy } else { 2000 + yy }, &text[2..])
+        }
+        TAG_GENERALIZED_TIME => (parse_digits(text, 0..4)?, &text[4..]),
+        _ => return None,
+    };
+
+    let month = parse_digits(rest, 0..2)?;
+    let day = parse_digits(rest, 2..4)?;
+    let hour = parse_digits(rest, 4..6)?;
+    let minute = parse_digits(rest, 6..8)?;
+    let second = parse_digits(rest, 8..10).unwrap_or(0);
+
+    let unix = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second;
+    let formatted = format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year, month, day, hour, minute, second
+    );
+    Some((unix, formatted))
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A leaf for test.vonogs.local signed by a throwaway CA, and that CA's
+    // own self-signed certificate, both P-256 and generated with openssl.
+    const LEAF: &[u8] = include_bytes!("../testdata/test-leaf.der");
+    const CA: &[u8] = include_bytes!("../testdata/test-ca.der");
+
+    #[test]
+    fn parses_leaf_certificate() {
+        let cert = parse_certificate(LEAF).expect("leaf parses");
+        assert_eq!(cert.subject, "O=Vonogs Test, CN=test.vonogs.local");
+        assert_eq!(cert.issuer, "C=DE, O=Vonogs Test, CN=Vonogs Test CA");
+        assert_eq!(
+            cert.sans,
+            ["test.vonogs.local", "www.test.vonogs.local", "127.0.0.1"]
+        );
+        assert_eq!(cert.not_after, "2035-06-01 12:00:00 UTC");
+        assert_eq!(cert.not_after_unix, 2_064_312_000);
+        assert!(!cert.self_signed);
+    }
+
+    #[test]
+    fn parses_self_signed_certificate() {
+        let cert = parse_certificate(CA).expect("CA parses");
+        assert_eq!(cert.subject, "C=DE, O=Vonogs Test, CN=Vonogs Test CA");
+        assert_eq!(cert.issuer, cert.subject);
+        assert!(cert.sans.is_empty());
+        // After 2049 the validity is a GeneralizedTime instead of UTCTime.
+        assert_eq!(cert.not_after, "2051-01-01 00:00:00 UTC");
+        assert_eq!(cert.not_after_unix, 2_556_144_000);
+        assert!(cert.self_signed);
+    }
+
+    #[test]
+    fn rejects_truncated_certificate() {
+        assert!(parse_certificate(&LEAF[..LEAF.len() - 1]).is_none());
+        assert!(parse_certificate(&LEAF[..10]).is_none());
+        assert!(parse_certificate(&[]).is_none());
+    }
+
+    #[test]
+    fn counts_days_from_epoch() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+    }
+}
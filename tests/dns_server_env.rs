@@ -0,0 +1,23 @@
+use std::process::Command;
+
+fn vonogs(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_vonogs"))
+        .args(args)
+        .env("VONOGS_DNS_SERVER", "not-a-server")
+        .output()
+        .unwrap()
+}
+
+/// A bad `VONOGS_DNS_SERVER` only stops commands that resolve names.
+#[test]
+fn dns_server_is_checked_only_when_needed() {
+    let help = vonogs(&["help"]);
+    assert_eq!(help.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&help.stdout).starts_with("Usage:"));
+
+    let scan = vonogs(&["scan", "127.0.0.1", "-p", "1", "--quiet"]);
+    assert_eq!(scan.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&scan.stderr).contains(
+        "VONOGS_DNS_SERVER must be an IP address with an optional port, not 'not-a-server'"
+    ));
+}
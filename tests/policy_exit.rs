@@ -0,0 +1,50 @@
+use std::fs;
+use std::net::TcpListener;
+use std::process::Command;
+
+/// Runs `vonogs policy` on `policy` in a scratch directory and returns its
+/// exit code.
+fn policy_exit_code(name: &str, policy: &str) -> i32 {
+    let dir = std::env::temp_dir().join(format!("vonogs-policy-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("policy.conf"), policy).unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_vonogs"))
+        .args(["policy", "policy.conf"])
+        .current_dir(&dir)
+        .output()
+        .unwrap()
+        .status;
+    fs::remove_dir_all(&dir).unwrap();
+    status.code().unwrap()
+}
+
+#[test]
+fn exit_code_reflects_the_policy_outcome() {
+    let open = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = open.local_addr().unwrap().port();
+    let host = |settings: &str| {
+        format!(
+            "[host 127.0.0.1]\nprofile = quick\nports = {}\n{}\n",
+            port, settings
+        )
+    };
+
+    // Allowing the quick profile keeps other local services from failing it.
+    let pass = host(&format!(
+        "allow = 21-23,25,53,80,110,143,443,445,993,995,1723,3306,3389,5900,8080\nrequire = {}",
+        port
+    ));
+    assert_eq!(policy_exit_code("pass", &pass), 0);
+    assert_eq!(policy_exit_code("violation", &host("")), 1);
+    assert_eq!(
+        policy_exit_code(
+            "unusable",
+            &format!("{}[host nothing.invalid]\nallow = 22\n", host(""))
+        ),
+        2
+    );
+    assert_eq!(
+        policy_exit_code("invalid", "[host 127.0.0.1]\nallow = 0\n"),
+        2
+    );
+}
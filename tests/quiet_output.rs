@@ -0,0 +1,48 @@
+use std::fs;
+use std::net::TcpListener;
+use std::process::Command;
+
+/// `scan --quiet` is piped into other tools, so its stdout must hold only
+/// the open `host:port` lines, even when the log cannot be written and a
+/// webhook fails.
+#[test]
+fn quiet_scan_prints_only_open_ports() {
+    let open = TcpListener::bind("127.0.0.1:0").unwrap();
+    let open_port = open.local_addr().unwrap().port();
+    let closed_port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    // A file where the log directory belongs makes every log unwritable.
+    let dir = std::env::temp_dir().join(format!("vonogs-quiet-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("scan_logs"), "").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vonogs"))
+        .args(["scan", "127.0.0.1", "-p"])
+        .arg(format!("{},{}", open_port, closed_port))
+        .arg("--quiet")
+        .current_dir(&dir)
+        .env(
+            "VONOGS_WEBHOOK_URL",
+            format!("http://127.0.0.1:{}/", closed_port),
+        )
+        .env("VONOGS_WEBHOOK_RETRIES", "0")
+        .env("VONOGS_WEBHOOK_BACKOFF_MS", "0")
+        .output()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(
+        stdout,
+        format!("127.0.0.1:{}\n", open_port),
+        "stderr: {}",
+        stderr
+    );
+    assert!(stderr.contains("Could not create log file"), "{}", stderr);
+    assert!(stderr.contains("webhook scan_completed"), "{}", stderr);
+}